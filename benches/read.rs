@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compares peak heap usage of [`Format::read`] to reading the file into memory and parsing it
+//! using [`Format::from_slice`], e.g. `cargo bench --bench read --features memmap2`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::fs;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use confargs::{Format, Toml};
+
+/// [`System`] allocator, which tracks the current and peak number of allocated bytes.
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let n = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            let _ = PEAK.fetch_max(n, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        let _ = CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Returns the peak number of bytes allocated by `f` in addition to those allocated before.
+fn peak(f: impl FnOnce()) -> usize {
+    let base = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    f();
+    PEAK.load(Ordering::Relaxed) - base
+}
+
+fn main() {
+    let mut config = String::new();
+    for i in 0..100_000 {
+        writeln!(config, "key{i} = \"{}\"", "x".repeat(64)).unwrap();
+    }
+    let dir = tempfile::tempdir().expect("failed to create temporary directory");
+    let path = dir.path().join("config.toml");
+    fs::write(&path, &config).expect("failed to write config");
+    drop(config);
+
+    let from_slice = peak(|| {
+        let buf = fs::read(&path).unwrap();
+        let _ = black_box(Toml::from_slice(buf).unwrap());
+    });
+    let read = peak(|| {
+        let _ = black_box(Toml::read(&path).unwrap());
+    });
+    let size = fs::metadata(&path).unwrap().len();
+    println!("config size: {size} bytes");
+    println!("fs::read + from_slice peak heap: {from_slice} bytes");
+    println!(
+        "read peak heap ({}): {read} bytes",
+        if cfg!(feature = "memmap2") {
+            "memmap2"
+        } else {
+            "fs::read"
+        }
+    );
+}
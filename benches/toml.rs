@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use confargs::{Format, Toml};
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use toml::value::Table;
+
+const SCALAR_CONFIG: &str = r#"string = "foo"
+integer = 42
+float = 42.2
+true = true
+false = false
+datetime = 1979-05-27T07:32:00Z
+"#;
+
+const NESTED_CONFIG: &str = r#"array = [1, 2, 3]
+
+[server]
+host = "localhost"
+port = 8080
+
+[[plugins]]
+name = "a"
+
+[[plugins]]
+name = "b"
+"#;
+
+fn from_slice(c: &mut Criterion) {
+    let _ = c
+        .bench_function("scalar", |b| {
+            b.iter(|| Toml::from_slice(black_box(SCALAR_CONFIG)).unwrap())
+        })
+        .bench_function("nested", |b| {
+            b.iter(|| Toml::from_slice(black_box(NESTED_CONFIG)).unwrap())
+        });
+}
+
+fn from_table(c: &mut Criterion) {
+    let scalar: Table = toml::from_str(SCALAR_CONFIG).unwrap();
+    let nested: Table = toml::from_str(NESTED_CONFIG).unwrap();
+    let _ = c
+        .bench_function("scalar_table", |b| {
+            b.iter_batched(
+                || scalar.clone(),
+                |kv| Toml::from_table(kv).unwrap(),
+                BatchSize::SmallInput,
+            )
+        })
+        .bench_function("nested_table", |b| {
+            b.iter_batched(
+                || nested.clone(),
+                |kv| Toml::from_table(kv).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+}
+
+criterion_group!(benches, from_slice, from_table);
+criterion_main!(benches);
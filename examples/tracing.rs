@@ -0,0 +1,16 @@
+use anyhow::Context;
+use confargs::{prefix_char_filter, Toml};
+use tracing::Level;
+
+fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_max_level(Level::TRACE)
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+
+    let args =
+        confargs::args::<Toml>(prefix_char_filter::<'@'>).context("failed to parse config")?;
+    println!("{:?}", args.collect::<Vec<_>>());
+    Ok(())
+}
@@ -0,0 +1,268 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{args_from_with, merge, Format, Options};
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::{env, io};
+
+/// Boxed argument filter, see [`Filter`](crate::Filter).
+type BoxFilter = Box<dyn Fn(&str) -> Option<&Path>>;
+
+/// Source of arguments added to [`ConfigBuilder`].
+enum Layer {
+    Embedded(&'static [u8]),
+    SearchPath(Vec<PathBuf>),
+    EnvVar(String),
+    File(PathBuf),
+    Cli(BoxFilter, Vec<String>),
+}
+
+impl fmt::Debug for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Embedded(buf) => f.debug_tuple("Embedded").field(buf).finish(),
+            Self::SearchPath(paths) => f.debug_tuple("SearchPath").field(paths).finish(),
+            Self::EnvVar(var) => f.debug_tuple("EnvVar").field(var).finish(),
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Cli(_, args) => f.debug_tuple("Cli").field(&"..").field(args).finish(),
+        }
+    }
+}
+
+/// Builder assembling arguments from layered sources using [`Format`] and [`Options`].
+///
+/// Sources are added in order of increasing precedence, e.g. embedded defaults, a search path,
+/// environment variables, explicit files and finally command-line arguments. Arguments of each
+/// source are [`merge`]d with arguments of all previously added sources, such that flags of a
+/// source replace all flags with the same key of previous sources.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{prefix_char_filter, ConfigBuilder, Toml};
+///
+/// let args = ConfigBuilder::<Toml>::new()
+///     .add_embedded(br#"string = "default"
+/// integer = 42"#)
+///     .add_search_path(["mytool.toml", "/etc/mytool/config.toml"])
+///     .add_env_var("MYTOOL_CONFIG")
+///     .add_cli(prefix_char_filter::<'@'>, ["mytool".into(), "--string=cli".into()])
+///     .build()
+///     .expect("failed to build arguments");
+/// assert_eq!(args, vec!["mytool", "--integer=42", "--string=cli"]);
+/// ```
+#[derive(Debug)]
+pub struct ConfigBuilder<T> {
+    layers: Vec<Layer>,
+    opts: Options,
+    format: PhantomData<fn() -> T>,
+}
+
+impl<T: Format> Default for ConfigBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Format> ConfigBuilder<T> {
+    /// Returns [`ConfigBuilder`] without any sources using default [`Options`].
+    pub fn new() -> Self {
+        Self {
+            layers: vec![],
+            opts: Options::default(),
+            format: PhantomData,
+        }
+    }
+
+    /// Sets [`Options`] used to parse all sources.
+    pub fn options(mut self, opts: Options) -> Self {
+        self.opts = opts;
+        self
+    }
+
+    /// Adds embedded configuration `buf`, e.g. one compiled in using [`include_bytes`].
+    pub fn add_embedded(mut self, buf: &'static [u8]) -> Self {
+        self.layers.push(Layer::Embedded(buf));
+        self
+    }
+
+    /// Adds the first existing configuration file of `paths`. Nothing is added if none of the
+    /// files exist.
+    pub fn add_search_path(mut self, paths: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        self.layers.push(Layer::SearchPath(
+            paths.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Adds the configuration file at path specified by environment variable `var`. Nothing
+    /// is added if `var` is not set or empty, but it is an error for the file to not exist.
+    pub fn add_env_var(mut self, var: impl Into<String>) -> Self {
+        self.layers.push(Layer::EnvVar(var.into()));
+        self
+    }
+
+    /// Adds the configuration file at `path`. It is an error for the file to not exist.
+    pub fn add_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.layers.push(Layer::File(path.into()));
+        self
+    }
+
+    /// Adds command-line arguments `args`, e.g. [`std::env::args`], with all configuration
+    /// files paths returned by [`Filter`](crate::Filter) expanded in place. The first
+    /// argument is expected to be the binary name, which is the first argument built.
+    pub fn add_cli(
+        mut self,
+        f: impl Fn(&str) -> Option<&Path> + 'static,
+        args: impl IntoIterator<Item = String>,
+    ) -> Self {
+        self.layers
+            .push(Layer::Cli(Box::new(f), args.into_iter().collect()));
+        self
+    }
+
+    /// Reads all sources in order they were added and returns the merged arguments.
+    pub fn build(self) -> io::Result<Vec<String>> {
+        let Self { layers, opts, .. } = self;
+        let read = |path: &PathBuf| {
+            T::read_with(path, &opts)
+                .map(|args| args.into_iter().collect::<Vec<_>>())
+                .map_err(|e| {
+                    io::Error::new(
+                        e.kind(),
+                        format!("Failed to parse config at `{}`: {e}", path.display()),
+                    )
+                })
+        };
+        let mut bin = None;
+        let mut out = vec![];
+        for layer in layers {
+            let args = match layer {
+                Layer::Embedded(buf) => T::from_slice_with(buf, &opts)
+                    .map_err(|e| {
+                        io::Error::new(e.kind(), format!("Failed to parse embedded config: {e}"))
+                    })?
+                    .into_iter()
+                    .collect(),
+                Layer::SearchPath(paths) => match paths.iter().find(|path| path.is_file()) {
+                    Some(path) => read(path)?,
+                    None => vec![],
+                },
+                Layer::EnvVar(var) => match env::var_os(&var).filter(|path| !path.is_empty()) {
+                    Some(path) => read(&path.into())?,
+                    None => vec![],
+                },
+                Layer::File(path) => read(&path)?,
+                Layer::Cli(f, args) => {
+                    let mut args = args.into_iter();
+                    bin = args.next();
+                    args_from_with::<T>(&*f, args, &opts)?.collect()
+                }
+            };
+            out = merge(out, args);
+        }
+        Ok(bin.into_iter().chain(out).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{prefix_char_filter, Separator, Toml};
+
+    use std::fs;
+
+    use clap::Parser;
+
+    #[test]
+    fn build() {
+        #[derive(Clone, Debug, Parser, PartialEq)]
+        struct Args {
+            #[clap(long)]
+            string: String,
+            #[clap(long)]
+            integer: u8,
+            #[clap(long)]
+            float: f32,
+        }
+
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let file = dir.path().join("file.toml");
+        let cli = dir.path().join("cli.toml");
+        fs::write(&file, "string = \"file\"\ninteger = 2").expect("failed to write config");
+        fs::write(&cli, "integer = 3").expect("failed to write config");
+
+        let args = ConfigBuilder::<Toml>::new()
+            .add_embedded(b"string = \"embedded\"\ninteger = 1\nfloat = 1.5")
+            .add_search_path([dir.path().join("missing.toml"), file.clone()])
+            .add_cli(
+                prefix_char_filter::<'@'>,
+                [
+                    "test".into(),
+                    format!("@{}", cli.display()),
+                    "--string=cli".into(),
+                ],
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            args,
+            vec!["test", "--float=1.5", "--integer=3", "--string=cli"]
+        );
+        assert_eq!(
+            Args::try_parse_from(args).unwrap(),
+            Args {
+                string: "cli".into(),
+                integer: 3,
+                float: 1.5,
+            }
+        );
+
+        assert_eq!(
+            ConfigBuilder::<Toml>::new()
+                .add_embedded(b"string = \"embedded\"")
+                .add_file(&file)
+                .build()
+                .unwrap(),
+            vec!["--integer=2", "--string=file"]
+        );
+        assert_eq!(
+            ConfigBuilder::<Toml>::new()
+                .add_file(dir.path().join("missing.toml"))
+                .build()
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn build_separate() {
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let file = dir.path().join("file.toml");
+        fs::write(&file, "name = \"file\"\ntrailing = [\"-x\"]").expect("failed to write config");
+
+        let prefix = String::from("--config=");
+        let args = ConfigBuilder::<Toml>::new()
+            .options(Options {
+                separator: Separator::Space,
+                trailing_key: Some("trailing".into()),
+                ..Default::default()
+            })
+            .add_embedded(b"name = \"embedded\"\ninteger = 1")
+            .add_file(&file)
+            .add_cli(
+                move |arg| arg.strip_prefix(prefix.as_str()).map(Path::new),
+                ["test".into(), "--name".into(), "cli".into()],
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            args,
+            vec!["test", "--integer", "1", "--name", "cli", "--", "-x"]
+        );
+    }
+}
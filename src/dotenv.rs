@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{strip_bom, Format, Options, Toml};
+
+use std::io;
+
+use toml::value::{Table, Value};
+
+/// Returns numbered lines of `buf`, starting at 1, with `\r\n` and `\n` line endings removed.
+fn lines(buf: &str) -> impl Iterator<Item = (usize, &str)> {
+    buf.split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+}
+
+/// Parses value `v` of the line at `n`, unquoting it, if quoted.
+///
+/// `\"`, `\\` and `\n` escapes are replaced within `"`-quoted values, other escapes are kept
+/// unchanged. `'`-quoted values are taken literally.
+fn parse_value(n: usize, v: &str) -> io::Result<String> {
+    let invalid = |msg| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("failed to parse dotenv: {msg} at line {n}"),
+        )
+    };
+    match v.chars().next() {
+        Some('"') => {
+            let mut out = String::with_capacity(v.len());
+            let mut chars = v[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, c @ ('"' | '\\'))) => out.push(c),
+                        Some((_, 'n')) => out.push('\n'),
+                        Some((_, c)) => {
+                            out.push('\\');
+                            out.push(c);
+                        }
+                        None => return Err(invalid("unterminated quoted value")),
+                    },
+                    Some((_, c)) => out.push(c),
+                    None => return Err(invalid("unterminated quoted value")),
+                }
+            };
+            let rest = v[end..].trim_start();
+            if !rest.is_empty() && !rest.starts_with('#') {
+                return Err(invalid("unexpected characters after quoted value"));
+            }
+            Ok(out)
+        }
+        Some('\'') => {
+            let v = &v[1..];
+            let end = v
+                .find('\'')
+                .ok_or_else(|| invalid("unterminated quoted value"))?;
+            let rest = v[end + 1..].trim_start();
+            if !rest.is_empty() && !rest.starts_with('#') {
+                return Err(invalid("unexpected characters after quoted value"));
+            }
+            Ok(v[..end].into())
+        }
+        _ => Ok(v.split_once(" #").map_or(v, |(v, _)| v).trim_end().into()),
+    }
+}
+
+/// [dotenv](https://github.com/motdotla/dotenv) configuration file format.
+///
+/// This format expects lines of form `KEY=value`, optionally prefixed by `export`, renders
+/// values as strings the same way as [`Toml`] does and uses keys unchanged. Values may be
+/// quoted using `"`, within which `\"`, `\\` and `\n` escapes are supported, or literally using
+/// `'`. Lines starting with `#` and empty lines are ignored. Both `\n` and
+/// `\r\n` line endings are supported and whitespace surrounding keys and unquoted values is
+/// trimmed.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{Dotenv, Format};
+///
+/// assert_eq!(
+///     Dotenv::from_slice(
+///         r#"# database
+/// DB_HOST=localhost
+/// export DB_NAME = "my db"
+/// "#
+///         .as_bytes()
+///     )
+///     .unwrap(),
+///     vec!["--DB_HOST=localhost", "--DB_NAME=my db"]
+/// );
+/// ```
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub struct Config;
+
+impl Format for Config {
+    type IntoIter = Vec<String>;
+
+    fn extensions() -> &'static [&'static str] {
+        &["env"]
+    }
+
+    fn from_slice(buf: impl AsRef<[u8]>) -> io::Result<Self::IntoIter> {
+        Self::from_slice_with(buf, &Options::default())
+    }
+
+    fn from_slice_with(buf: impl AsRef<[u8]>, opts: &Options) -> io::Result<Self::IntoIter> {
+        let buf = std::str::from_utf8(strip_bom(buf.as_ref())).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("failed to parse dotenv: {e}"),
+            )
+        })?;
+        let mut kv = Table::new();
+        for (n, line) in lines(buf) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line
+                .strip_prefix("export")
+                .filter(|line| line.starts_with(char::is_whitespace))
+                .unwrap_or(line);
+            let (k, v) = line.split_once('=').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("failed to parse dotenv: missing `=` at line {n}"),
+                )
+            })?;
+            let k = k.trim();
+            if k.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("failed to parse dotenv: empty key at line {n}"),
+                ));
+            }
+            let _ = kv.insert(k.into(), Value::String(parse_value(n, v.trim())?));
+        }
+        Toml::from_table_with(kv, opts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_slice() {
+        assert_eq!(
+            Config::from_slice(
+                "# comment\n\nexport A=1\nB = two words # comment\nC='#quoted' # comment\nD=\nexportE=e"
+            )
+            .unwrap(),
+            vec!["--A=1", "--B=two words", "--C=#quoted", "--D=", "--exportE=e"]
+        );
+        assert!(Config::from_slice("A").is_err());
+        assert!(Config::from_slice("=1").is_err());
+        assert!(Config::from_slice("A=\"1").is_err());
+        assert!(Config::from_slice("A=\"1\" 2").is_err());
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            Config::from_slice(
+                r#"A="a\"b" # comment
+B="c\\"
+C="d\ne\t"
+D='f\"g'
+"#
+            )
+            .unwrap(),
+            vec!["--A=a\"b", "--B=c\\", "--C=d\ne\\t", "--D=f\\\"g"]
+        );
+        assert!(Config::from_slice(r#"A="a\""#).is_err());
+        assert!(Config::from_slice(r#"A="a\"#).is_err());
+    }
+
+    #[test]
+    fn crlf() {
+        assert_eq!(
+            Config::from_slice(
+                "# comment\r\n  HOST = localhost  \r\nNAME=\"foo bar\"\r\n\r\nPORT=8080\r\n"
+            )
+            .unwrap(),
+            vec!["--HOST=localhost", "--NAME=foo bar", "--PORT=8080"]
+        );
+        assert_eq!(
+            Config::from_slice("A=1\r\nB").unwrap_err().to_string(),
+            "failed to parse dotenv: missing `=` at line 2"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt;
+
+/// Position within a configuration source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// Column number, starting at 1.
+    pub column: usize,
+}
+
+impl Position {
+    /// Computes the position of byte `offset` within `buf`.
+    pub(crate) fn from_offset(buf: &[u8], offset: usize) -> Self {
+        let buf = &buf[..offset.min(buf.len())];
+        let line_start = buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        Self {
+            line: buf.iter().filter(|&&b| b == b'\n').count() + 1,
+            column: String::from_utf8_lossy(&buf[line_start..]).chars().count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Kind of a configuration value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueKind {
+    /// String value.
+    String,
+    /// Integer value.
+    Integer,
+    /// Float value.
+    Float,
+    /// Boolean value.
+    Boolean,
+    /// Datetime value.
+    Datetime,
+    /// Array value.
+    Array,
+    /// Table value.
+    Table,
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Error produced for a configuration value, which cannot be converted into arguments.
+///
+/// This error is returned wrapped in an [`io::Error`](std::io::Error) of
+/// [`InvalidData`](std::io::ErrorKind::InvalidData) kind and can be retrieved using
+/// [`get_ref`](std::io::Error::get_ref).
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{Error, Format, Position, Toml, ValueKind};
+///
+/// let err = Toml::from_slice("key = [[1, 2]]").unwrap_err();
+/// let err = err.get_ref().and_then(|e| e.downcast_ref::<Error>()).unwrap();
+/// assert_eq!(err.key, "key.0");
+/// assert_eq!(err.kind, Some(ValueKind::Array));
+/// assert_eq!(err.position, Some(Position { line: 1, column: 8 }));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    /// Flattened key of the offending value, e.g. `server.port`.
+    pub key: String,
+    /// Description of the error.
+    pub message: String,
+    /// Position of the offending value within the source, if known.
+    pub position: Option<Position>,
+    /// Kind of the offending value, if the error is caused by the kind of the value, e.g. an
+    /// array nested within an array.
+    pub kind: Option<ValueKind>,
+}
+
+impl Error {
+    pub(crate) fn new(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            message: message.into(),
+            position: None,
+            kind: None,
+        }
+    }
+
+    /// Sets the kind of the offending value.
+    pub(crate) fn with_kind(self, kind: ValueKind) -> Self {
+        Self {
+            kind: Some(kind),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(position) = self.position {
+            write!(f, "{} at {position}", self.message)
+        } else {
+            write!(f, "{}", self.message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{strip_bom, Error, Format, Options, Toml, ValueKind};
+
+use std::borrow::Cow;
+use std::io;
+
+use serde_json::Value as JsonValue;
+use toml::value::{Table, Value};
+
+/// Converts JSON value `v` at flattened key `k` into a TOML value, returns `None` if `v` is
+/// `null`. It is an error for integers to be out of range of [`i64`].
+fn to_toml(k: &str, v: JsonValue) -> Result<Option<Value>, Error> {
+    match v {
+        JsonValue::Null => Ok(None),
+        JsonValue::Bool(v) => Ok(Some(Value::Boolean(v))),
+        JsonValue::Number(v) if v.is_u64() && !v.is_i64() => Err(Error::new(
+            k,
+            format!("integer `{v}` of field `{k}` is out of range of 64-bit signed integers"),
+        )
+        .with_kind(ValueKind::Integer)),
+        JsonValue::Number(v) => Ok(v
+            .as_i64()
+            .map(Value::Integer)
+            .or_else(|| v.as_f64().map(Value::Float))),
+        JsonValue::String(v) => Ok(Some(Value::String(v))),
+        JsonValue::Array(vs) => vs
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, v)| to_toml(&format!("{k}.{i}"), v).transpose())
+            .collect::<Result<_, _>>()
+            .map(|vs| Some(Value::Array(vs))),
+        JsonValue::Object(kv) => to_toml_table(Some(k), kv).map(|kv| Some(Value::Table(kv))),
+    }
+}
+
+fn to_toml_table(
+    prefix: Option<&str>,
+    kv: serde_json::Map<String, JsonValue>,
+) -> Result<Table, Error> {
+    kv.into_iter()
+        .filter_map(|(k, v)| {
+            let key = prefix.map_or_else(|| k.clone(), |prefix| format!("{prefix}.{k}"));
+            to_toml(&key, v).map(|v| v.map(|v| (k, v))).transpose()
+        })
+        .collect()
+}
+
+/// Replaces `//` line comments and `/* */` block comments outside of strings in `buf` by spaces,
+/// which preserves positions of all other characters. Unterminated block comments are left
+/// unchanged to be rejected by the parser.
+fn strip_comments(buf: &[u8]) -> Cow<'_, [u8]> {
+    if !buf.contains(&b'/') {
+        return Cow::Borrowed(buf);
+    }
+    let mut out = buf.to_vec();
+    let mut i = 0;
+    while i < out.len() {
+        match (out[i], out.get(i + 1)) {
+            (b'"', _) => {
+                i += 1;
+                while i < out.len() && out[i] != b'"' {
+                    i += if out[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            (b'/', Some(b'/')) => {
+                while i < out.len() && out[i] != b'\n' {
+                    out[i] = b' ';
+                    i += 1;
+                }
+            }
+            (b'/', Some(b'*')) => {
+                let Some(n) = out[i + 2..].windows(2).position(|w| w == b"*/") else {
+                    break;
+                };
+                for b in &mut out[i..i + n + 4] {
+                    if !b.is_ascii_whitespace() {
+                        *b = b' ';
+                    }
+                }
+                i += n + 3;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Cow::Owned(out)
+}
+
+/// [JSON](https://www.json.org/) configuration file format.
+///
+/// This format expects the configuration to be represented as an object and renders values
+/// the same way as [`Toml`] does. `null` values are ignored and it is an error for integers to be
+/// out of range of [`i64`]. `//` and `/* */` comments are allowed if [`Options::json_comments`]
+/// is set.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{Format, Json};
+///
+/// assert_eq!(
+///     Json::from_slice(
+///         r#"{
+///     "string": "foo",
+///     "integer": 42,
+///     "float": 42.2,
+///     "true": true,
+///     "false": false,
+///     "null": null,
+///     "array": [1, 2, 3]
+/// }"#
+///         .as_bytes()
+///     )
+///     .unwrap(),
+///     vec![
+///         "--array=1",
+///         "--array=2",
+///         "--array=3",
+///         "--float=42.2",
+///         "--integer=42",
+///         "--string=foo",
+///         "--true",
+///     ]
+/// );
+/// ```
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub struct Config;
+
+impl Format for Config {
+    type IntoIter = Vec<String>;
+
+    fn extensions() -> &'static [&'static str] {
+        &["json"]
+    }
+
+    fn from_slice(buf: impl AsRef<[u8]>) -> io::Result<Self::IntoIter> {
+        Self::from_slice_with(buf, &Options::default())
+    }
+
+    fn from_slice_with(buf: impl AsRef<[u8]>, opts: &Options) -> io::Result<Self::IntoIter> {
+        let buf = strip_bom(buf.as_ref());
+        let buf = if opts.json_comments {
+            strip_comments(buf)
+        } else {
+            Cow::Borrowed(buf)
+        };
+        match serde_json::from_slice(&buf).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("failed to parse JSON: {e}"),
+            )
+        })? {
+            JsonValue::Object(kv) => Toml::from_table_with(
+                to_toml_table(None, kv)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+                opts,
+            ),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid config file format",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_slice() {
+        assert_eq!(
+            Config::from_slice(
+                r#"{
+    "server": { "port": 8080, "host": null },
+    "plugins": [{ "name": "a" }, null, { "name": "b" }],
+    "float": 1.5
+}"#
+            )
+            .unwrap(),
+            vec![
+                "--float=1.5",
+                "--plugins.0.name=a",
+                "--plugins.1.name=b",
+                "--server.port=8080",
+            ]
+        );
+        assert!(Config::from_slice("[1, 2]").is_err());
+        let err = Config::from_slice(r#"{"a": {"b": [1, 18446744073709551615]}}"#).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<Error>())
+            .unwrap();
+        assert_eq!(err.key, "a.b.1");
+        assert_eq!(err.kind, Some(ValueKind::Integer));
+        assert!(Config::from_slice("{").is_err());
+    }
+
+    #[test]
+    fn bom() {
+        assert_eq!(
+            Config::from_slice(b"\xEF\xBB\xBF{\"integer\": 42}").unwrap(),
+            vec!["--integer=42"]
+        );
+    }
+
+    #[test]
+    fn numeric_positionals() {
+        let opts = Options {
+            numeric_positionals: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(r#"{"10": "c", "2": true, "0": "a", "1": 1.5}"#, &opts)
+                .unwrap(),
+            vec!["a", "1.5", "true", "c"]
+        );
+        assert_eq!(
+            Config::from_slice_with(r#"{"0": "a", "name": "b"}"#, &opts).unwrap(),
+            vec!["--0=a", "--name=b"]
+        );
+        assert_eq!(
+            Config::from_slice(r#"{"0": "a", "1": "b"}"#).unwrap(),
+            vec!["--0=a", "--1=b"]
+        );
+        assert!(Config::from_slice_with(r#"{"0": [1]}"#, &opts).is_err());
+        assert_eq!(
+            Config::from_slice_with(r#"{"1": "a", "01": "b", "+2": "c"}"#, &opts).unwrap(),
+            vec!["--+2=c", "--01=b", "--1=a"]
+        );
+        assert_eq!(
+            Config::from_slice_with(
+                r#"{"0": "/opt", "1": "{0}/data"}"#,
+                &Options {
+                    interpolate: true,
+                    ..opts.clone()
+                }
+            )
+            .unwrap(),
+            vec!["/opt", "/opt/data"]
+        );
+        assert!(Config::from_slice_with(
+            r#"{"0": "a", "1": "b"}"#,
+            &Options {
+                known_keys: Some(["0".into()].into()),
+                ..opts
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn comments() {
+        const CONFIG: &str = r#"{
+    // line comment
+    "url": "https://example.com", // trailing comment
+    /* block
+       comment */ "path": "a/*b*/c",
+    "escaped": "a\"//b\\" // comment after escaped backslash
+}"#;
+
+        assert!(Config::from_slice(CONFIG).is_err());
+        let opts = Options {
+            json_comments: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &opts).unwrap(),
+            vec![
+                r#"--escaped=a"//b\"#,
+                "--path=a/*b*/c",
+                "--url=https://example.com"
+            ]
+        );
+        let err = Config::from_slice_with("{\n/* unterminated\n}", &opts).unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{err}");
+    }
+}
@@ -4,7 +4,8 @@
 //!
 //! The main use case for this crate is to add configuration file support for CLI tools and argument parsers, which do not have support for configuration files (for example, [clap](https://github.com/clap-rs/clap))
 
-#![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "memmap2"), forbid(unsafe_code))]
+#![cfg_attr(feature = "memmap2", deny(unsafe_code))]
 #![deny(
     clippy::all,
     absolute_paths_not_starting_with_crate,
@@ -31,21 +32,133 @@
     variant_size_differences
 )]
 
+/// Emits a [`tracing`](https://docs.rs/tracing) event at level `$level`, if `tracing` feature is
+/// enabled. Otherwise, the event, including its fields, is not evaluated.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+mod builder;
+#[cfg(feature = "dotenv")]
+mod dotenv;
+mod error;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "clap")]
+mod matches;
+mod naming;
+mod options;
+pub mod prelude;
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod toml;
 
+pub use self::builder::ConfigBuilder;
+#[cfg(feature = "dotenv")]
+pub use self::dotenv::Config as Dotenv;
+pub use self::error::{Error, Position, ValueKind};
+#[cfg(feature = "json")]
+pub use self::json::Config as Json;
+#[cfg(feature = "clap")]
+pub use self::matches::{merge_matches, validate_against};
+pub use self::naming::{Identity, KebabCase, NamingStrategy, Prefixed};
+pub use self::options::{
+    ArrayBool, ArrayFlag, BoolTable, EmptyArray, Options, PairTable, Separator, TableArray,
+};
 pub use self::toml::Config as Toml;
 
-use std::fmt::Display;
-use std::fs::read;
-use std::path::Path;
-use std::{env, io};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::iter::FusedIterator;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+/// Contents of a configuration file.
+enum Contents {
+    Vec(Vec<u8>),
+    #[cfg(feature = "memmap2")]
+    Map(memmap2::Mmap),
+}
+
+impl AsRef<[u8]> for Contents {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Vec(buf) => buf,
+            #[cfg(feature = "memmap2")]
+            Self::Map(buf) => buf,
+        }
+    }
+}
+
+/// Reads the contents of the configuration file at `path`.
+///
+/// If `memmap2` feature is enabled, uncompressed regular files are memory-mapped instead of being
+/// read. Other files, e.g. pipes, and files, which cannot be mapped, are read. If `remote`
+/// feature is enabled, `http://` and `https://` URLs are fetched. On Unix, paths of form `fd:N`
+/// refer to the inherited file descriptor `N`, which is reopened as `/dev/fd/N`. On Linux, this
+/// opens the underlying file anew, so regular files are read from the start rather than from the
+/// current offset of the descriptor. Descriptors, which cannot be reopened, e.g. sockets, are not
+/// supported.
+fn read(path: impl AsRef<Path>) -> io::Result<Contents> {
+    let path = path.as_ref();
+    event!(debug, path = %path.display(), "reading configuration file");
+    #[cfg(feature = "remote")]
+    if let Some(url) = path.to_str().filter(|path| remote::is_url(path)) {
+        return remote::fetch(url).map(Contents::Vec);
+    }
+    #[cfg(unix)]
+    if let Some(fd) = path
+        .to_str()
+        .and_then(|path| path.strip_prefix("fd:"))
+        .and_then(|fd| fd.parse::<u32>().ok())
+    {
+        return fs::read(format!("/dev/fd/{fd}")).map(Contents::Vec);
+    }
+    #[cfg(feature = "flate2")]
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        use std::io::Read;
+
+        let mut buf = vec![];
+        let _ = flate2::read::GzDecoder::new(fs::File::open(path)?)
+            .read_to_end(&mut buf)
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("failed to decompress `{}`: {e}", path.display()),
+                )
+            })?;
+        return Ok(Contents::Vec(buf));
+    }
+    #[cfg(feature = "memmap2")]
+    {
+        use std::io::Read;
 
-fn parse_string_arg(k: impl Display, v: impl Display) -> String {
-    format!("--{k}={v}")
+        let mut file = fs::File::open(path)?;
+        if file.metadata()?.is_file() {
+            // SAFETY: The mapping is only used for the duration of parsing, which produces owned
+            // arguments. Modification of the file by another process while it is being parsed may
+            // result in invalid arguments or a parsing error.
+            #[allow(unsafe_code)]
+            if let Ok(buf) = unsafe { memmap2::Mmap::map(&file) } {
+                return Ok(Contents::Map(buf));
+            }
+        }
+        let mut buf = vec![];
+        let _ = file.read_to_end(&mut buf)?;
+        Ok(Contents::Vec(buf))
+    }
+    #[cfg(not(feature = "memmap2"))]
+    fs::read(path).map(Contents::Vec)
 }
 
-fn parse_bool_arg(k: impl Display, v: bool) -> Option<String> {
-    v.then(|| format!("--{k}"))
+/// Strips a leading UTF-8 byte-order mark from `buf`, if present.
+fn strip_bom(buf: &[u8]) -> &[u8] {
+    buf.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(buf)
 }
 
 /// Configuration file format
@@ -54,17 +167,281 @@ pub trait Format {
     type IntoIter: IntoIterator<Item = String>;
 
     /// Reads configuration at `path` and returns an [`IntoIter`](Self::IntoIter) of arguments
+    ///
+    /// If `flate2` feature is enabled, files with `.gz` extension are transparently decompressed.
+    /// If `memmap2` feature is enabled, other regular files are memory-mapped instead of being
+    /// read into memory. On Unix, `path` of form `fd:N`, e.g. `fd:3`, refers to an inherited file descriptor
+    /// `N`, from which the configuration is read. The descriptor is reopened as `/dev/fd/N`, which
+    /// on Linux reads regular files from the start and does not support sockets. If `remote`
+    /// feature is enabled, `path` of form `http://...` or `https://...` is fetched with a 30
+    /// second timeout and a size limit of 16 MiB. It is an error for the response to not have
+    /// a successful status code.
     fn read(path: impl AsRef<Path>) -> io::Result<Self::IntoIter> {
-        read(path).and_then(|buf| Self::from_slice(buf.as_slice()))
+        read(path).and_then(Self::from_slice)
+    }
+
+    /// Reads configuration at `path` using [`Options`] and returns an
+    /// [`IntoIter`](Self::IntoIter) of arguments
+    ///
+    /// If `flate2` feature is enabled, files with `.gz` extension are transparently decompressed.
+    /// If `memmap2` feature is enabled, other regular files are memory-mapped instead of being
+    /// read into memory. On Unix, `path` of form `fd:N`, e.g. `fd:3`, refers to an inherited file descriptor
+    /// `N`, from which the configuration is read. The descriptor is reopened as `/dev/fd/N`, which
+    /// on Linux reads regular files from the start and does not support sockets. If `remote`
+    /// feature is enabled, `path` of form `http://...` or `https://...` is fetched with a 30
+    /// second timeout and a size limit of 16 MiB. It is an error for the response to not have
+    /// a successful status code.
+    fn read_with(path: impl AsRef<Path>, opts: &Options) -> io::Result<Self::IntoIter> {
+        read(path).and_then(|buf| Self::from_slice_with(buf, opts))
     }
 
     /// Parses configuration in `buf` and returns an [`IntoIter`](Self::IntoIter) of arguments
     fn from_slice(buf: impl AsRef<[u8]>) -> io::Result<Self::IntoIter>;
+
+    /// Returns the canonical file extensions of the format without the leading `.`, which are
+    /// used to choose the format of configuration files by extension, e.g. in [`args_auto`].
+    ///
+    /// The default implementation returns no extensions.
+    fn extensions() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Parses configuration in `buf` using [`Options`] and returns an
+    /// [`IntoIter`](Self::IntoIter) of arguments.
+    ///
+    /// The default implementation ignores `opts` and calls [`from_slice`](Self::from_slice).
+    fn from_slice_with(buf: impl AsRef<[u8]>, opts: &Options) -> io::Result<Self::IntoIter> {
+        let _ = opts;
+        Self::from_slice(buf)
+    }
+}
+
+/// Object-safe configuration file format, which allows for [`Format`] to be chosen at runtime.
+///
+/// This trait is implemented for all [`Format`] implementations.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{DynFormat, Options, Toml};
+///
+/// let format: Box<dyn DynFormat> = Box::new(Toml);
+/// assert_eq!(
+///     format
+///         .parse_dyn(r#"string = "foo""#.as_bytes(), &Options::default())
+///         .unwrap(),
+///     vec!["--string=foo"]
+/// );
+/// ```
+pub trait DynFormat {
+    /// Reads configuration at `path` using [`Options`] and returns a [`Vec`] of arguments
+    fn read_dyn(&self, path: &Path, opts: &Options) -> io::Result<Vec<String>>;
+
+    /// Parses configuration in `buf` using [`Options`] and returns a [`Vec`] of arguments
+    fn parse_dyn(&self, buf: &[u8], opts: &Options) -> io::Result<Vec<String>>;
+
+    /// Returns the canonical file extensions of the format, see [`Format::extensions`]
+    fn extensions_dyn(&self) -> &'static [&'static str];
+}
+
+impl<T: Format> DynFormat for T {
+    fn read_dyn(&self, path: &Path, opts: &Options) -> io::Result<Vec<String>> {
+        T::read_with(path, opts).map(|args| args.into_iter().collect())
+    }
+
+    fn parse_dyn(&self, buf: &[u8], opts: &Options) -> io::Result<Vec<String>> {
+        T::from_slice_with(buf, opts).map(|args| args.into_iter().collect())
+    }
+
+    fn extensions_dyn(&self) -> &'static [&'static str] {
+        T::extensions()
+    }
+}
+
+/// Registry of supported configuration file formats.
+static FORMATS: &[&(dyn DynFormat + Sync)] = &[
+    &Toml,
+    #[cfg(feature = "json")]
+    &Json,
+    #[cfg(feature = "dotenv")]
+    &Dotenv,
+];
+
+/// Returns the format within `formats` of configuration file at `path` based on its extension.
+fn format_of<'a, F: DynFormat + ?Sized>(path: &Path, formats: &[&'a F]) -> io::Result<&'a F> {
+    let ext = path.extension().and_then(|ext| ext.to_str());
+    formats
+        .iter()
+        .find(|format| ext.is_some_and(|ext| format.extensions_dyn().contains(&ext)))
+        .copied()
+        .ok_or_else(|| {
+            let supported = formats
+                .iter()
+                .flat_map(|format| format.extensions_dyn())
+                .map(|ext| format!("`.{ext}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "unsupported config file extension of `{}`, supported extensions are: {supported}",
+                    path.display()
+                ),
+            )
+        })
+}
+
+/// Parses configuration in `buf` using the first format within `formats`, which parses it
+/// successfully, and returns a [`Vec`] of arguments.
+///
+/// This is useful when the format cannot be determined by extension. Formats are attempted in
+/// order of `formats` and if all of them fail, an error describing all failures is returned.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::Toml;
+///
+/// assert_eq!(
+///     confargs::try_formats(&[&Toml], br#"string = "foo""#).unwrap(),
+///     vec!["--string=foo"]
+/// );
+/// assert!(confargs::try_formats(&[&Toml], b"{").is_err());
+/// ```
+pub fn try_formats(formats: &[&dyn DynFormat], buf: &[u8]) -> io::Result<Vec<String>> {
+    try_formats_with(formats, buf, &Options::default())
+}
+
+/// Parses configuration in `buf` using [`Options`] and the first format within `formats`, which
+/// parses it successfully, and returns a [`Vec`] of arguments.
+///
+/// See [`try_formats`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{Options, Toml};
+///
+/// assert_eq!(
+///     confargs::try_formats_with(&[&Toml], br#"string = "foo""#, &Options::default()).unwrap(),
+///     vec!["--string=foo"]
+/// );
+/// ```
+pub fn try_formats_with(
+    formats: &[&dyn DynFormat],
+    buf: &[u8],
+    opts: &Options,
+) -> io::Result<Vec<String>> {
+    let mut errs = Vec::with_capacity(formats.len());
+    for (i, format) in formats.iter().enumerate() {
+        match format.parse_dyn(buf, opts) {
+            Ok(args) => return Ok(args),
+            Err(e) => match format.extensions_dyn().first() {
+                Some(ext) => errs.push(format!("`{ext}`: {e}")),
+                None => errs.push(format!("format {i}: {e}")),
+            },
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "failed to parse configuration using any format: {}",
+            errs.join(", ")
+        ),
+    ))
 }
 
+/// Prefix of a directive on the first line of a configuration file, which specifies its format.
+const FORMAT_DIRECTIVE: &str = "#!confargs ";
+
+/// Reads the configuration file at `path` using the format within `formats` chosen by its
+/// extension or by a format directive, if [`Options::format_directive`] is set.
+fn read_auto<F: DynFormat + ?Sized>(
+    path: &Path,
+    opts: &Options,
+    formats: &[&F],
+) -> io::Result<Vec<String>> {
+    if !opts.format_directive {
+        return format_of(path, formats)?.read_dyn(path, opts);
+    }
+    let buf = read(path)?;
+    let buf = buf.as_ref();
+    let Some(directive) = buf.strip_prefix(FORMAT_DIRECTIVE.as_bytes()) else {
+        return format_of(path, formats)?.parse_dyn(buf, opts);
+    };
+    let end = directive
+        .iter()
+        .position(|&b| b == b'\n')
+        .unwrap_or(directive.len());
+    let (name, rest) = directive.split_at(end);
+    let name = String::from_utf8_lossy(name);
+    let name = name.trim();
+    formats
+        .iter()
+        .find(|format| format.extensions_dyn().contains(&name))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "unsupported config file format `{name}` specified by directive of `{}`",
+                    path.display()
+                ),
+            )
+        })?
+        .parse_dyn(rest, opts)
+}
+
+/// Iterator over arguments produced by expanding configuration files.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::prefix_char_filter;
+///
+/// let args = confargs::args_from::<confargs::Toml>(
+///     prefix_char_filter::<'@'>,
+///     ["test".into(), "--foo".into()].into_iter(),
+/// )
+/// .expect("failed to parse configuration files");
+/// assert_eq!(args.len(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Args(std::vec::IntoIter<String>);
+
+impl From<Vec<String>> for Args {
+    fn from(args: Vec<String>) -> Self {
+        Self(args.into_iter())
+    }
+}
+
+impl Iterator for Args {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Args {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl ExactSizeIterator for Args {}
+
+impl FusedIterator for Args {}
+
 /// Argument filter, which, given a command-line argument, either returns `Some(path)`, if the
 /// argument is a path to configuration file or returns `None` otherwise.
 ///
+/// Functions accepting a filter take any `impl Fn(&str) -> Option<&Path>`, therefore closures
+/// capturing their environment can be used as well.
+///
 /// # Examples
 ///
 /// ```
@@ -75,18 +452,138 @@ pub trait Format {
 /// ```
 pub type Filter = fn(&str) -> Option<&Path>;
 
+/// Argument filter, which, given a command-line argument, either returns `Some(paths)`, if the
+/// argument refers to one or more configuration files or returns `None` otherwise.
+///
+/// Configuration files are parsed in order of `paths`. Same as with [`Filter`], closures
+/// capturing their environment can be used as well.
+///
+/// # Examples
+///
+/// ```
+/// # use confargs::MultiFilter;
+/// use std::path::PathBuf;
+///
+/// let _: MultiFilter = |arg| {
+///     arg.strip_prefix("--config=")
+///         .map(|paths| paths.split(',').map(PathBuf::from).collect())
+/// };
+/// ```
+pub type MultiFilter = fn(&str) -> Option<Vec<PathBuf>>;
+
+/// Fallible argument filter, which, given a command-line argument, either returns
+/// `Ok(Some(path))`, if the argument is a path to configuration file, `Ok(None)`, if it is not
+/// or an error, if the argument refers to a configuration file, but is malformed.
+///
+/// Same as with [`Filter`], closures capturing their environment can be used as well.
+///
+/// # Examples
+///
+/// ```
+/// # use confargs::TryFilter;
+/// use std::io;
+/// use std::path::Path;
+///
+/// let _: TryFilter = |arg| match arg.strip_prefix("--config=") {
+///     Some("") => Err(io::Error::new(
+///         io::ErrorKind::InvalidInput,
+///         "empty configuration file path",
+///     )),
+///     path => Ok(path.map(Path::new)),
+/// };
+/// ```
+pub type TryFilter = fn(&str) -> io::Result<Option<&Path>>;
+
 /// Argument filter, which filters arguments by a character prefix.
 ///
+/// A doubled prefix escapes it, i.e. arguments starting with `C` twice are not treated as
+/// configuration file paths and are passed through with one `C` removed, e.g. `@@foo` becomes
+/// `@foo`.
+///
+/// Arguments consisting of the prefix only are not treated as configuration file paths either
+/// and need no escaping, i.e. both `@` and `@@` are passed through unchanged, see
+/// [`strict_prefix_char_filter`] for a filter returning an error for `@` instead.
+///
 /// # Examples
 ///
 /// ```
 /// # use confargs::prefix_char_filter;
 /// use confargs::Filter;
+/// use std::path::Path;
 ///
-/// let _: Filter = prefix_char_filter::<'@'>;
+/// let f: Filter = prefix_char_filter::<'@'>;
+/// assert_eq!(f("@foo"), Some(Path::new("foo")));
+/// assert_eq!(f("@@foo"), None);
+/// assert_eq!(f("@"), None);
 /// ```
 pub fn prefix_char_filter<const C: char>(arg: &str) -> Option<&Path> {
-    arg.strip_prefix(C).map(Path::new)
+    prefix_char_filter_runtime(C)(arg)
+}
+
+/// Argument filter, which filters arguments by a character prefix `c` chosen at runtime.
+///
+/// Same as [`prefix_char_filter`], a doubled prefix escapes it.
+///
+/// # Examples
+///
+/// ```
+/// # use confargs::prefix_char_filter_runtime;
+/// use std::path::Path;
+///
+/// let f = prefix_char_filter_runtime('+');
+/// assert_eq!(f("+foo"), Some(Path::new("foo")));
+/// assert_eq!(f("++foo"), None);
+/// assert_eq!(f("@foo"), None);
+/// ```
+pub fn prefix_char_filter_runtime(c: char) -> impl Fn(&str) -> Option<&Path> {
+    move |arg| {
+        arg.strip_prefix(c)
+            .filter(|path| !path.is_empty() && !path.starts_with(c))
+            .map(Path::new)
+    }
+}
+
+/// Fallible argument filter, which filters arguments by a character prefix and returns an
+/// error for arguments consisting of the prefix only.
+///
+/// Same as [`prefix_char_filter`], a doubled prefix escapes it, e.g. `@@foo` becomes `@foo`,
+/// and a doubled prefix alone, i.e. `@@`, is passed through unchanged.
+///
+/// # Examples
+///
+/// ```
+/// # use confargs::strict_prefix_char_filter;
+/// use confargs::TryFilter;
+/// use std::path::Path;
+///
+/// let f: TryFilter = strict_prefix_char_filter::<'@'>;
+/// assert_eq!(f("@foo").unwrap(), Some(Path::new("foo")));
+/// assert_eq!(f("foo").unwrap(), None);
+/// assert!(f("@").is_err());
+/// ```
+pub fn strict_prefix_char_filter<const C: char>(arg: &str) -> io::Result<Option<&Path>> {
+    match arg.strip_prefix(C) {
+        Some("") => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("missing configuration file path after `{C}`"),
+        )),
+        _ => Ok(prefix_char_filter::<C>(arg)),
+    }
+}
+
+/// Removes the escape from an argument `arg` not matched by a filter, if `arg` starts with a
+/// doubled prefix character, which the filter would match otherwise as determined by `f`.
+fn unescape(f: impl Fn(&str) -> bool, mut arg: String) -> String {
+    let mut chars = arg.chars();
+    if let (Some(c), Some(next)) = (chars.next(), chars.next()) {
+        if c == next {
+            let rest = arg.trim_start_matches(c);
+            if f(&format!("{c}{rest}")) {
+                let _ = arg.remove(0);
+            }
+        }
+    }
+    arg
 }
 
 /// Parses all configuration files paths returned by [`Filter`] from [`std::env::args`]
@@ -99,13 +596,75 @@ pub fn prefix_char_filter<const C: char>(arg: &str) -> Option<&Path> {
 /// let args = confargs::args::<Toml>(prefix_char_filter::<'@'>)
 ///     .expect("failed to parse configuration files");
 /// ```
-pub fn args<T: Format>(f: Filter) -> io::Result<impl IntoIterator<Item = String>> {
+pub fn args<T: Format>(f: impl Fn(&str) -> Option<&Path>) -> io::Result<Args> {
     args_from::<T>(f, env::args())
 }
 
+/// Parses all configuration files paths returned by [`Filter`] from [`std::env::args`]
+/// using [`Format`] into a [`Vec`] of arguments.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Toml};
+///
+/// let args: Vec<String> = confargs::args_vec::<Toml>(prefix_char_filter::<'@'>)
+///     .expect("failed to parse configuration files");
+/// ```
+pub fn args_vec<T: Format>(f: impl Fn(&str) -> Option<&Path>) -> io::Result<Vec<String>> {
+    args_from_vec::<T>(f, env::args())
+}
+
+/// Parses all configuration files paths returned by [`Filter`] from an [`ExactSizeIterator`]
+/// using [`Format`] into a [`Vec`] of arguments.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Toml};
+///
+/// let args: Vec<String> =
+///     confargs::args_from_vec::<Toml>(prefix_char_filter::<'@'>, std::env::args())
+///         .expect("failed to parse configuration files");
+/// ```
+pub fn args_from_vec<T: Format>(
+    f: impl Fn(&str) -> Option<&Path>,
+    args: impl ExactSizeIterator<Item = String>,
+) -> io::Result<Vec<String>> {
+    expand(
+        |arg| Ok(f(arg).map(|path| vec![path.into()])),
+        args,
+        &Options::default(),
+        |path, opts| T::read_with(path, opts),
+    )
+    .map(Iterator::collect)
+}
+
+/// Parses all configuration files paths returned by [`Filter`] from [`std::env::args`]
+/// using [`Format`] and [`Options`] into an [`IntoIterator`] of arguments.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let args = confargs::args_with::<Toml>(
+///     prefix_char_filter::<'@'>,
+///     &Options {
+///         root: Some("mytool".into()),
+///         ..Default::default()
+///     },
+/// )
+/// .expect("failed to parse configuration files");
+/// ```
+pub fn args_with<T: Format>(f: impl Fn(&str) -> Option<&Path>, opts: &Options) -> io::Result<Args> {
+    args_from_with::<T>(f, env::args(), opts)
+}
+
 /// Parses all configuration files paths returned by [`Filter`] from an [`ExactSizeIterator`]
 /// using [`Format`] into an [`IntoIterator`] of arguments.
 ///
+/// Arguments parsed from a configuration file replace the argument referring to it exactly
+/// where it appeared, preserving the order of all surrounding arguments. This holds for all
+/// functions in this crate expanding configuration file arguments.
+///
 /// # Examples
 /// ```
 /// use confargs::{prefix_char_filter, Toml};
@@ -114,130 +673,2432 @@ pub fn args<T: Format>(f: Filter) -> io::Result<impl IntoIterator<Item = String>
 ///     .expect("failed to parse configuration files");
 /// ```
 pub fn args_from<T: Format>(
-    f: Filter,
-    mut args: impl ExactSizeIterator<Item = String>,
-) -> io::Result<impl IntoIterator<Item = String>> {
-    args.try_fold(Vec::with_capacity(args.len()), |mut args, arg| {
-        if let Some(path) = f(&arg) {
-            T::read(path)
-                .map_err(|e| {
-                    io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        format!("Failed to parse config at `{}`: {e}", path.display()),
-                    )
-                })?
-                .into_iter()
-                .for_each(|arg| args.push(arg));
-        } else {
-            args.push(arg);
-        }
-        Ok(args)
-    })
+    f: impl Fn(&str) -> Option<&Path>,
+    args: impl ExactSizeIterator<Item = String>,
+) -> io::Result<Args> {
+    args_from_with::<T>(f, args, &Options::default())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Parses all configuration files paths returned by [`Filter`] from an [`ExactSizeIterator`]
+/// using [`Format`] and [`Options`] into an [`IntoIterator`] of arguments.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let args = confargs::args_from_with::<Toml>(
+///     prefix_char_filter::<'@'>,
+///     std::env::args(),
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// ```
+pub fn args_from_with<T: Format>(
+    f: impl Fn(&str) -> Option<&Path>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Args> {
+    expand(
+        |arg| Ok(f(arg).map(|path| vec![path.into()])),
+        args,
+        opts,
+        |path, opts| T::read_with(path, opts),
+    )
+}
 
-    use std::io::Write;
-    use std::iter::once;
-    use std::process::Command;
+/// Parses all configuration files paths returned by [`TryFilter`] from [`std::env::args`]
+/// using [`Format`] and [`Options`] into an [`IntoIterator`] of arguments.
+///
+/// Errors returned by the filter are propagated.
+///
+/// # Examples
+/// ```
+/// use confargs::{strict_prefix_char_filter, Options, Toml};
+///
+/// let args =
+///     confargs::args_try_with::<Toml>(strict_prefix_char_filter::<'@'>, &Options::default())
+///         .expect("failed to parse configuration files");
+/// ```
+pub fn args_try_with<T: Format>(
+    f: impl Fn(&str) -> io::Result<Option<&Path>>,
+    opts: &Options,
+) -> io::Result<Args> {
+    args_from_try_with::<T>(f, env::args(), opts)
+}
 
-    use clap::Parser;
-    use tempfile::NamedTempFile;
+/// Parses all configuration files paths returned by [`TryFilter`] from an
+/// [`ExactSizeIterator`] using [`Format`] and [`Options`] into an [`IntoIterator`] of arguments.
+///
+/// Errors returned by the filter are propagated.
+///
+/// # Examples
+/// ```
+/// use confargs::{strict_prefix_char_filter, Options, Toml};
+///
+/// let err = confargs::args_from_try_with::<Toml>(
+///     strict_prefix_char_filter::<'@'>,
+///     ["test".into(), "@".into()].into_iter(),
+///     &Options::default(),
+/// )
+/// .err()
+/// .unwrap();
+/// assert_eq!(err.to_string(), "missing configuration file path after `@`");
+/// ```
+pub fn args_from_try_with<T: Format>(
+    f: impl Fn(&str) -> io::Result<Option<&Path>>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Args> {
+    expand(
+        |arg| Ok(f(arg)?.map(|path| vec![path.into()])),
+        args,
+        opts,
+        |path, opts| T::read_with(path, opts),
+    )
+}
 
-    const TOML_CONFIG: &str = r#"#Test config
-string = "foo"
-integer = 42
-float = 42.2
-true = true
-false = false
-datetime = 01:02:03.000000004
-array = [1, 2, 3]"#;
+/// Parses all configuration files paths returned by [`MultiFilter`] from [`std::env::args`]
+/// using [`Format`] into an [`IntoIterator`] of arguments.
+///
+/// # Examples
+/// ```
+/// use confargs::Toml;
+/// use std::path::PathBuf;
+///
+/// let args = confargs::args_multi::<Toml>(|arg| {
+///     arg.strip_prefix('@')
+///         .map(|paths| paths.split(',').map(PathBuf::from).collect())
+/// })
+/// .expect("failed to parse configuration files");
+/// ```
+pub fn args_multi<T: Format>(f: impl Fn(&str) -> Option<Vec<PathBuf>>) -> io::Result<Args> {
+    args_from_multi_with::<T>(f, env::args(), &Options::default())
+}
 
-    #[test]
-    fn args() {
-        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
-        assert_eq!(
-            conf.write(TOML_CONFIG.as_bytes())
-                .expect("failed to write config"),
-            TOML_CONFIG.len()
-        );
+/// Parses all configuration files paths returned by [`MultiFilter`] from an
+/// [`ExactSizeIterator`] using [`Format`] and [`Options`] into an [`IntoIterator`] of arguments.
+///
+/// # Examples
+/// ```
+/// use confargs::{Options, Toml};
+/// use std::path::PathBuf;
+///
+/// let args = confargs::args_from_multi_with::<Toml>(
+///     |arg| {
+///         arg.strip_prefix('@')
+///             .map(|paths| paths.split(',').map(PathBuf::from).collect())
+///     },
+///     std::env::args(),
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// ```
+pub fn args_from_multi_with<T: Format>(
+    f: impl Fn(&str) -> Option<Vec<PathBuf>>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Args> {
+    expand(
+        |arg| Ok(f(arg)),
+        args,
+        opts,
+        |path, opts| T::read_with(path, opts),
+    )
+}
 
-        const PRINT_ARGS: &str = env!("CARGO_BIN_FILE_PRINT_ARGS");
-        let out = Command::new(PRINT_ARGS)
-            .args([
-                "--test",
-                &format!("@{}", conf.path().display()),
-                "foo",
-                "--bar=baz",
-            ])
-            .output()
-            .unwrap();
-        assert_eq!(String::from_utf8(out.stderr).unwrap(), "");
-        assert_eq!(
-            String::from_utf8(out.stdout).unwrap(),
-            format!(
-                r#"{PRINT_ARGS}
---test
---array=1
---array=2
---array=3
---datetime=01:02:03.000000004
---float=42.2
---integer=42
---string=foo
---true
-foo
---bar=baz
-"#,
-            )
-        );
+/// Parses all configuration files paths returned by [`Filter`] from [`std::env::args`]
+/// into an [`IntoIterator`] of arguments, using the [`Format`] chosen by file extension
+/// of each path.
+///
+/// Supported extensions depend on enabled features:
+/// - `.toml` - [`Toml`]
+/// - `.json` - `Json`, requires `json` feature
+/// - `.env` - `Dotenv`, requires `dotenv` feature
+///
+/// # Examples
+/// ```
+/// use confargs::prefix_char_filter;
+///
+/// let args = confargs::args_auto(prefix_char_filter::<'@'>)
+///     .expect("failed to parse configuration files");
+/// ```
+pub fn args_auto(f: impl Fn(&str) -> Option<&Path>) -> io::Result<Args> {
+    args_from_auto_with(f, env::args(), &Options::default())
+}
+
+/// Parses all configuration files paths returned by [`Filter`] from an [`ExactSizeIterator`]
+/// using [`Options`] into an [`IntoIterator`] of arguments, using the [`Format`] chosen by file
+/// extension of each path.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options};
+///
+/// let args = confargs::args_from_auto_with(
+///     prefix_char_filter::<'@'>,
+///     std::env::args(),
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// ```
+pub fn args_from_auto_with(
+    f: impl Fn(&str) -> Option<&Path>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Args> {
+    expand(
+        |arg| Ok(f(arg).map(|path| vec![path.into()])),
+        args,
+        opts,
+        |path, opts| read_auto(path, opts, FORMATS),
+    )
+}
+
+/// Parses all configuration files paths returned by [`Filter`] from an [`ExactSizeIterator`]
+/// using [`Options`] into an [`IntoIterator`] of arguments, using the format within `formats`
+/// chosen by file extension of each path as returned by [`Format::extensions`].
+///
+/// This allows for custom [`Format`] implementations to be used. Formats are matched in order
+/// of `formats`.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let args = confargs::args_from_auto_formats_with(
+///     prefix_char_filter::<'@'>,
+///     std::env::args(),
+///     &Options::default(),
+///     &[&Toml],
+/// )
+/// .expect("failed to parse configuration files");
+/// ```
+pub fn args_from_auto_formats_with(
+    f: impl Fn(&str) -> Option<&Path>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+    formats: &[&dyn DynFormat],
+) -> io::Result<Args> {
+    expand(
+        |arg| Ok(f(arg).map(|path| vec![path.into()])),
+        args,
+        opts,
+        |path, opts| read_auto(path, opts, formats),
+    )
+}
+
+/// Parses the configuration file at path specified by environment variable `var` and all
+/// configuration files paths returned by [`Filter`] from [`std::env::args`] using [`Format`]
+/// into an [`IntoIterator`] of arguments.
+///
+/// See [`args_from_env_var_with`] for details.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Toml};
+///
+/// let args = confargs::args_from_env_var::<Toml>("MYTOOL_CONFIG", prefix_char_filter::<'@'>)
+///     .expect("failed to parse configuration files");
+/// ```
+pub fn args_from_env_var<T: Format>(
+    var: &str,
+    f: impl Fn(&str) -> Option<&Path>,
+) -> io::Result<Args> {
+    args_from_env_var_with::<T>(var, f, env::args(), &Options::default())
+}
+
+/// Parses the configuration file at path specified by environment variable `var` and all
+/// configuration files paths returned by [`Filter`] from an [`ExactSizeIterator`] using
+/// [`Format`] and [`Options`] into an [`IntoIterator`] of arguments.
+///
+/// Arguments parsed from the configuration file specified by `var` follow the first argument,
+/// which is expected to be the binary name, and precede all other arguments. Nothing is
+/// prepended if `var` is not set or empty, but it is an error for the file to not exist, even if
+/// [`Options::skip_missing`] is set.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let args = confargs::args_from_env_var_with::<Toml>(
+///     "MYTOOL_CONFIG",
+///     prefix_char_filter::<'@'>,
+///     std::env::args(),
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// ```
+pub fn args_from_env_var_with<T: Format>(
+    var: &str,
+    f: impl Fn(&str) -> Option<&Path>,
+    mut args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Args> {
+    let path = env::var_os(var).filter(|path| !path.is_empty());
+    let config = match path.map(PathBuf::from) {
+        Some(path) => T::read_with(&path, opts)
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!(
+                        "Failed to parse config at `{}` specified by `{var}`: {e}",
+                        path.display()
+                    ),
+                )
+            })?
+            .into_iter()
+            .collect(),
+        None => vec![],
+    };
+    let bin = args.next();
+    let rest = args_from_with::<T>(f, args, opts)?;
+    Ok(Args::from(
+        bin.into_iter()
+            .chain(config)
+            .chain(rest)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Parses embedded configuration `buf`, e.g. one compiled in using [`include_bytes`], and all
+/// configuration files paths returned by [`Filter`] from [`std::env::args`] using [`Format`]
+/// into an [`IntoIterator`] of arguments.
+///
+/// See [`args_from_embedded_with`] for details.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Toml};
+///
+/// let args = confargs::args_with_embedded::<Toml>(
+///     prefix_char_filter::<'@'>,
+///     include_bytes!("../examples/conf.toml"),
+/// )
+/// .expect("failed to parse configuration files");
+/// ```
+pub fn args_with_embedded<T: Format>(
+    f: impl Fn(&str) -> Option<&Path>,
+    buf: &[u8],
+) -> io::Result<Args> {
+    args_from_embedded_with::<T>(f, buf, env::args(), &Options::default())
+}
+
+/// Parses embedded configuration `buf` and all configuration files paths returned by
+/// [`Filter`] from an [`ExactSizeIterator`] using [`Format`] and [`Options`] into an
+/// [`IntoIterator`] of arguments.
+///
+/// Arguments parsed from `buf` serve as defaults, they follow the first argument, which is
+/// expected to be the binary name, and are [`merge`]d with all other arguments, such that flags
+/// with the same key from configuration files or the command line take precedence. Separate
+/// values of overridden flags, e.g. those rendered using [`Separator::Space`], are dropped
+/// along with them.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let args = confargs::args_from_embedded_with::<Toml>(
+///     prefix_char_filter::<'@'>,
+///     br#"string = "default"
+/// integer = 42"#,
+///     ["test".into(), "--string=cli".into()].into_iter(),
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// assert_eq!(
+///     args.collect::<Vec<_>>(),
+///     vec!["test", "--integer=42", "--string=cli"]
+/// );
+/// ```
+pub fn args_from_embedded_with<T: Format>(
+    f: impl Fn(&str) -> Option<&Path>,
+    buf: &[u8],
+    mut args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Args> {
+    let embedded = T::from_slice_with(buf, opts)
+        .map_err(|e| io::Error::new(e.kind(), format!("Failed to parse embedded config: {e}")))?;
+    let bin = args.next();
+    let rest = args_from_with::<T>(f, args, opts)?;
+    Ok(Args::from(
+        bin.into_iter()
+            .chain(merge(embedded, rest))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Returns the path of `file` within the platform-specific configuration directory of
+/// application `app`, e.g. `$XDG_CONFIG_HOME/app/file` on Linux,
+/// `~/Library/Application Support/app/file` on macOS and `%APPDATA%\app\config\file` on
+/// Windows. Returns `None` if the home directory cannot be determined. Requires `directories`
+/// feature.
+///
+/// # Examples
+/// ```
+/// let path = confargs::config_dir_path("mytool", "config.toml");
+/// ```
+#[cfg(feature = "directories")]
+pub fn config_dir_path(app: &str, file: impl AsRef<Path>) -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", app).map(|dirs| dirs.config_dir().join(file))
+}
+
+/// Parses the configuration file `file` within the platform-specific configuration directory
+/// of application `app` and all configuration files paths returned by [`Filter`] from
+/// [`std::env::args`] using [`Format`] into an [`IntoIterator`] of arguments. Requires
+/// `directories` feature.
+///
+/// See [`args_from_config_dir_with`] for details.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Toml};
+///
+/// let args =
+///     confargs::args_from_config_dir::<Toml>("mytool", "config.toml", prefix_char_filter::<'@'>)
+///         .expect("failed to parse configuration files");
+/// ```
+#[cfg(feature = "directories")]
+pub fn args_from_config_dir<T: Format>(
+    app: &str,
+    file: impl AsRef<Path>,
+    f: impl Fn(&str) -> Option<&Path>,
+) -> io::Result<Args> {
+    args_from_config_dir_with::<T>(app, file, f, env::args(), &Options::default())
+}
+
+/// Parses the configuration file `file` within the platform-specific configuration directory
+/// of application `app` and all configuration files paths returned by [`Filter`] from an
+/// [`ExactSizeIterator`] using [`Format`] and [`Options`] into an [`IntoIterator`] of
+/// arguments. Requires `directories` feature.
+///
+/// The configuration file path is determined using [`config_dir_path`]. Arguments parsed from
+/// it follow the first argument, which is expected to be the binary name, and precede all other
+/// arguments. Nothing is prepended if the file does not exist.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let args = confargs::args_from_config_dir_with::<Toml>(
+///     "mytool",
+///     "config.toml",
+///     prefix_char_filter::<'@'>,
+///     std::env::args(),
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// ```
+#[cfg(feature = "directories")]
+pub fn args_from_config_dir_with<T: Format>(
+    app: &str,
+    file: impl AsRef<Path>,
+    f: impl Fn(&str) -> Option<&Path>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Args> {
+    args_from_path_with::<T>(config_dir_path(app, file), f, args, opts)
+}
+
+/// Returns the path of `file` within the directory containing the executable `exe`.
+fn exe_relative_path(exe: &Path, file: impl AsRef<Path>) -> PathBuf {
+    exe.parent().unwrap_or(Path::new("")).join(file)
+}
+
+/// Returns the path of `file` within the directory containing the current executable as
+/// returned by [`std::env::current_exe`].
+///
+/// # Examples
+/// ```
+/// let path = confargs::exe_dir_path("config.toml").expect("failed to get executable path");
+/// ```
+pub fn exe_dir_path(file: impl AsRef<Path>) -> io::Result<PathBuf> {
+    env::current_exe().map(|exe| exe_relative_path(&exe, file))
+}
+
+/// Parses the configuration file `file` within the directory containing the current executable
+/// and all configuration files paths returned by [`Filter`] from [`std::env::args`] using
+/// [`Format`] into an [`IntoIterator`] of arguments.
+///
+/// See [`args_from_exe_dir_with`] for details.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Toml};
+///
+/// let args = confargs::args_from_exe_dir::<Toml>("config.toml", prefix_char_filter::<'@'>)
+///     .expect("failed to parse configuration files");
+/// ```
+pub fn args_from_exe_dir<T: Format>(
+    file: impl AsRef<Path>,
+    f: impl Fn(&str) -> Option<&Path>,
+) -> io::Result<Args> {
+    args_from_exe_dir_with::<T>(file, f, env::args(), &Options::default())
+}
+
+/// Parses the configuration file `file` within the directory containing the current executable
+/// and all configuration files paths returned by [`Filter`] from an [`ExactSizeIterator`] using
+/// [`Format`] and [`Options`] into an [`IntoIterator`] of arguments.
+///
+/// The configuration file path is determined using [`exe_dir_path`], which is useful for
+/// portable applications shipping a configuration file next to the binary. Arguments parsed
+/// from it follow the first argument, which is expected to be the binary name, and precede all
+/// other arguments. Nothing is prepended if the file does not exist.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let args = confargs::args_from_exe_dir_with::<Toml>(
+///     "config.toml",
+///     prefix_char_filter::<'@'>,
+///     std::env::args(),
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// ```
+pub fn args_from_exe_dir_with<T: Format>(
+    file: impl AsRef<Path>,
+    f: impl Fn(&str) -> Option<&Path>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Args> {
+    args_from_path_with::<T>(Some(exe_dir_path(file)?), f, args, opts)
+}
+
+/// Parses the configuration file at `path`, if any, and all configuration files paths returned
+/// by `f` from `args` prepending arguments parsed from `path` after the first argument. Nothing
+/// is prepended if the file does not exist.
+fn args_from_path_with<T: Format>(
+    path: Option<PathBuf>,
+    f: impl Fn(&str) -> Option<&Path>,
+    mut args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Args> {
+    let config = match path.map(|path| (T::read_with(&path, opts), path)) {
+        Some((Ok(config), _)) => config.into_iter().collect(),
+        Some((Err(e), _)) if e.kind() == io::ErrorKind::NotFound => vec![],
+        Some((Err(e), path)) => {
+            return Err(io::Error::new(
+                e.kind(),
+                format!("Failed to parse config at `{}`: {e}", path.display()),
+            ))
+        }
+        None => vec![],
+    };
+    let bin = args.next();
+    let rest = args_from_with::<T>(f, args, opts)?;
+    Ok(Args::from(
+        bin.into_iter()
+            .chain(config)
+            .chain(rest)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Parses all configuration files paths returned by [`Filter`] from [`std::env::args`]
+/// using [`Format`] and [`Options`] into an [`IntoIterator`] of arguments and the number of
+/// configuration files expanded.
+///
+/// Files skipped due to [`Options::skip_missing`] are not counted.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let (args, n) =
+///     confargs::args_with_count::<Toml>(prefix_char_filter::<'@'>, &Options::default())
+///         .expect("failed to parse configuration files");
+/// eprintln!("loaded {n} config files");
+/// ```
+pub fn args_with_count<T: Format>(
+    f: impl Fn(&str) -> Option<&Path>,
+    opts: &Options,
+) -> io::Result<(Args, usize)> {
+    args_from_with_count::<T>(f, env::args(), opts)
+}
+
+/// Parses all configuration files paths returned by [`Filter`] from an [`ExactSizeIterator`]
+/// using [`Format`] and [`Options`] into an [`IntoIterator`] of arguments and the number of
+/// configuration files expanded.
+///
+/// Files skipped due to [`Options::skip_missing`] are not counted.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let (args, n) = confargs::args_from_with_count::<Toml>(
+///     prefix_char_filter::<'@'>,
+///     std::env::args(),
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// eprintln!("loaded {n} config files");
+/// ```
+pub fn args_from_with_count<T: Format>(
+    f: impl Fn(&str) -> Option<&Path>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<(Args, usize)> {
+    expand_count(
+        |arg| Ok(f(arg).map(|path| vec![path.into()])),
+        args,
+        opts,
+        |path, opts| T::read_with(path, opts),
+    )
+}
+
+fn expand<T: IntoIterator<Item = String>>(
+    f: impl Fn(&str) -> io::Result<Option<Vec<PathBuf>>>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+    read: impl Fn(&Path, &Options) -> io::Result<T>,
+) -> io::Result<Args> {
+    expand_count(f, args, opts, read).map(|(args, _)| args)
+}
+
+/// Removes all occurrences of reserved flag `--flag=value` from `args` and returns the value of
+/// the last one, if any, where an empty value is returned as [`None`].
+fn take_flag(args: &mut Vec<String>, flag: &str) -> Option<Option<String>> {
+    let prefix = format!("--{flag}=");
+    let mut value = None;
+    args.retain(|arg| match arg.strip_prefix(&prefix) {
+        Some(v) => {
+            value = Some((!v.is_empty()).then(|| v.to_string()));
+            false
+        }
+        None => true,
+    });
+    value
+}
+
+/// Expands configuration files in `args` and returns the arguments along with the number of
+/// configuration files read.
+fn expand_count<T: IntoIterator<Item = String>>(
+    f: impl Fn(&str) -> io::Result<Option<Vec<PathBuf>>>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+    read: impl Fn(&Path, &Options) -> io::Result<T>,
+) -> io::Result<(Args, usize)> {
+    let mut out = Vec::with_capacity(args.len());
+    let sources = expand_each(f, args, opts, read, |arg, _| out.push(arg))?;
+    Ok((out.into(), sources.len()))
+}
+
+/// Expands configuration files in `args` calling `push` for each resulting argument along with
+/// the path of the configuration file it originates from, if any, and returns the paths of
+/// configuration files read in order.
+fn expand_each<T: IntoIterator<Item = String>>(
+    f: impl Fn(&str) -> io::Result<Option<Vec<PathBuf>>>,
+    args: impl Iterator<Item = String>,
+    opts: &Options,
+    read: impl Fn(&Path, &Options) -> io::Result<T>,
+    mut push: impl FnMut(String, Option<&Path>),
+) -> io::Result<Vec<PathBuf>> {
+    let mut args: Vec<_> = args.collect();
+    let root = opts
+        .root_flag
+        .as_deref()
+        .and_then(|flag| take_flag(&mut args, flag));
+    let profile = opts
+        .profile_flag
+        .as_deref()
+        .and_then(|flag| take_flag(&mut args, flag));
+    let opts = match (root, profile) {
+        (None, None) => Cow::Borrowed(opts),
+        (root, profile) => Cow::Owned(Options {
+            root: root.unwrap_or_else(|| opts.root.clone()),
+            profile: profile.unwrap_or_else(|| opts.profile.clone()),
+            ..opts.clone()
+        }),
+    };
+    let mut sources = vec![];
+    let mut seen = HashSet::new();
+    for arg in args {
+        let Some(paths) = f(&arg)? else {
+            event!(trace, arg, "argument not matched by filter");
+            push(unescape(|arg| matches!(f(arg), Ok(Some(_))), arg), None);
+            continue;
+        };
+        event!(debug, arg, ?paths, "argument matched by filter");
+        for path in paths {
+            if opts.dedup_paths
+                && !seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone()))
+            {
+                event!(debug, path = %path.display(), "skipping already expanded configuration file");
+                continue;
+            }
+            match read(&path, &opts) {
+                Err(e) if opts.skip_missing && e.kind() == io::ErrorKind::NotFound => {
+                    event!(debug, path = %path.display(), "skipping missing configuration file");
+                    continue;
+                }
+                res => res,
+            }
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Failed to parse config at `{}`: {e}", path.display()),
+                )
+            })?
+            .into_iter()
+            .for_each(|arg| push(arg, Some(&path)));
+            sources.push(path);
+        }
+    }
+    Ok(sources)
+}
+
+/// Origin of an argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// Argument passed through from the command line.
+    Cli,
+    /// Argument generated from the configuration file at the path.
+    Config(PathBuf),
+}
+
+/// Parses all configuration files paths returned by [`Filter`] from [`std::env::args`]
+/// using [`Format`] and [`Options`] into a [`Vec`] of arguments along with their [`Source`].
+///
+/// This is useful for debugging which configuration file produced each argument.
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Source, Toml};
+///
+/// let args = confargs::args_with_provenance::<Toml>(
+///     prefix_char_filter::<'@'>,
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// for (arg, source) in args {
+///     match source {
+///         Source::Cli => eprintln!("{arg} (cli)"),
+///         Source::Config(path) => eprintln!("{arg} ({})", path.display()),
+///     }
+/// }
+/// ```
+pub fn args_with_provenance<T: Format>(
+    f: impl Fn(&str) -> Option<&Path>,
+    opts: &Options,
+) -> io::Result<Vec<(String, Source)>> {
+    args_from_with_provenance::<T>(f, env::args(), opts)
+}
+
+/// Parses all configuration files paths returned by [`Filter`] from an [`ExactSizeIterator`]
+/// using [`Format`] and [`Options`] into a [`Vec`] of arguments along with their [`Source`].
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let args = confargs::args_from_with_provenance::<Toml>(
+///     prefix_char_filter::<'@'>,
+///     std::env::args(),
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// ```
+pub fn args_from_with_provenance<T: Format>(
+    f: impl Fn(&str) -> Option<&Path>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Vec<(String, Source)>> {
+    let mut out = Vec::with_capacity(args.len());
+    let _ = expand_each(
+        |arg| Ok(f(arg).map(|path| vec![path.into()])),
+        args,
+        opts,
+        |path, opts| T::read_with(path, opts),
+        |arg, path| {
+            out.push((
+                arg,
+                path.map_or(Source::Cli, |path| Source::Config(path.into())),
+            ))
+        },
+    )?;
+    Ok(out)
+}
+
+/// Fully expanded arguments along with the paths of configuration files read to produce them.
+///
+/// Unlike [`Args`], [`Expansion`] can be inspected, e.g. to implement a flag printing the
+/// generated command line, and turned into [`Args`] repeatedly without reading any
+/// configuration files again.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Expansion {
+    /// Expanded arguments.
+    pub args: Vec<String>,
+    /// Paths of configuration files read in order, excluding skipped and deduplicated paths.
+    pub sources: Vec<PathBuf>,
+}
+
+impl Expansion {
+    /// Returns [`Args`] iterating over a copy of the expanded arguments.
+    pub fn args(&self) -> Args {
+        self.args.clone().into()
+    }
+}
+
+impl From<Expansion> for Args {
+    fn from(expansion: Expansion) -> Self {
+        expansion.args.into()
+    }
+}
+
+/// Parses all configuration files paths returned by [`Filter`] from [`std::env::args`]
+/// using [`Format`] and [`Options`] into an [`Expansion`].
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let expansion = confargs::args_expanded::<Toml>(
+///     prefix_char_filter::<'@'>,
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// for path in &expansion.sources {
+///     eprintln!("read {}", path.display());
+/// }
+/// eprintln!("{}", expansion.args.join(" "));
+/// ```
+pub fn args_expanded<T: Format>(
+    f: impl Fn(&str) -> Option<&Path>,
+    opts: &Options,
+) -> io::Result<Expansion> {
+    args_from_expanded::<T>(f, env::args(), opts)
+}
+
+/// Parses all configuration files paths returned by [`Filter`] from an [`ExactSizeIterator`]
+/// using [`Format`] and [`Options`] into an [`Expansion`].
+///
+/// # Examples
+/// ```
+/// use confargs::{prefix_char_filter, Options, Toml};
+///
+/// let expansion = confargs::args_from_expanded::<Toml>(
+///     prefix_char_filter::<'@'>,
+///     ["test".into(), "--foo".into()].into_iter(),
+///     &Options::default(),
+/// )
+/// .expect("failed to parse configuration files");
+/// assert_eq!(expansion.args, ["test", "--foo"]);
+/// assert!(expansion.sources.is_empty());
+/// ```
+pub fn args_from_expanded<T: Format>(
+    f: impl Fn(&str) -> Option<&Path>,
+    args: impl ExactSizeIterator<Item = String>,
+    opts: &Options,
+) -> io::Result<Expansion> {
+    let mut out = Vec::with_capacity(args.len());
+    let sources = expand_each(
+        |arg| Ok(f(arg).map(|path| vec![path.into()])),
+        args,
+        opts,
+        |path, opts| T::read_with(path, opts),
+        |arg, _| out.push(arg),
+    )?;
+    Ok(Expansion { args: out, sources })
+}
+
+/// Returns the key of a `--key=value` or `--key` argument, if `arg` is a flag.
+fn flag_key(arg: &str) -> Option<&str> {
+    let arg = arg.strip_prefix("--")?;
+    let key = arg.split_once('=').map_or(arg, |(k, _)| k);
+    (!key.is_empty()).then_some(key)
+}
+
+/// Merges arguments parsed from configuration files with explicitly specified command-line
+/// arguments, such that command-line flags take precedence over configuration for the same key.
+///
+/// All `config` flags, for which a flag with the same key is present in `cli` before `--` are
+/// dropped, this includes all occurrences of repeated flags produced by arrays. Flags are
+/// recognized in `--key=value` and `--key` form, where all non-flag arguments following a
+/// `--key` flag are treated as its values and are dropped along with it, e.g. those rendered
+/// using [`Separator::Space`] or [`ArrayFlag::Once`]. Note, that positional arguments
+/// following a dropped boolean flag are therefore dropped as well.
+///
+/// The remaining `config` arguments are followed by `cli` arguments. Arguments following `--`
+/// in `config`, e.g. those rendered using [`Options::trailing_key`], are moved after all flags
+/// and precede arguments following `--` in `cli`, if any. Note, that `cli` should not contain
+/// the binary name, since it is not the first argument in the returned [`Vec`].
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{Format, Toml};
+///
+/// let config = Toml::from_slice(
+///     r#"string = "conf"
+/// integer = 42"#,
+/// )
+/// .expect("failed to parse configuration");
+/// assert_eq!(
+///     confargs::merge(config, ["--string=override".into()]),
+///     vec!["--integer=42", "--string=override"],
+/// );
+/// ```
+pub fn merge(
+    config: impl IntoIterator<Item = String>,
+    cli: impl IntoIterator<Item = String>,
+) -> Vec<String> {
+    let cli: Vec<_> = cli.into_iter().collect();
+    let keys: HashSet<_> = cli
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .filter_map(|arg| flag_key(arg))
+        .map(String::from)
+        .collect();
+    merge_by(config, cli, |k| keys.contains(k))
+}
+
+/// Merges `config` with `cli` dropping all `config` flags, for which `drop` returns `true`,
+/// along with their separate values. Arguments following `--` in `config` are moved after all
+/// flags of `cli`.
+fn merge_by(
+    config: impl IntoIterator<Item = String>,
+    mut cli: Vec<String>,
+    drop: impl Fn(&str) -> bool,
+) -> Vec<String> {
+    let mut config: Vec<_> = config.into_iter().collect();
+    let config_trailing = config
+        .iter()
+        .position(|arg| arg == "--")
+        .map(|i| config.split_off(i + 1));
+    let cli_trailing = cli
+        .iter()
+        .position(|arg| arg == "--")
+        .map(|i| cli.split_off(i + 1));
+    let mut out = Vec::with_capacity(config.len() + cli.len());
+    let mut dropping = false;
+    for arg in config.into_iter().take_while(|arg| arg != "--") {
+        match flag_key(&arg) {
+            Some(k) => {
+                let dropped = drop(k);
+                dropping = dropped && arg.len() == k.len() + 2;
+                if !dropped {
+                    out.push(arg);
+                }
+            }
+            None if dropping => {}
+            None => out.push(arg),
+        }
+    }
+    out.extend(cli.into_iter().take_while(|arg| arg != "--"));
+    if config_trailing.is_some() || cli_trailing.is_some() {
+        out.push("--".into());
+        out.extend(config_trailing.into_iter().flatten());
+        out.extend(cli_trailing.into_iter().flatten());
+    }
+    out
+}
+
+/// Groups arguments `args` by flag key into a map, which can be compared to determine whether
+/// two sets of arguments are equivalent, e.g. to decide whether a reload of a changed
+/// configuration file is necessary.
+///
+/// Each flag is stored in full within the entry of its key in order of occurrence. Non-flag
+/// arguments are stored within the entry of the preceding flag, which renders values of flags
+/// separated by a space, and within the entry of the empty key if no flag precedes them or they
+/// follow `--`.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{Format, Toml};
+///
+/// let old = Toml::from_slice(
+///     r#"string = "foo"
+/// integer = 42"#,
+/// )
+/// .expect("failed to parse configuration");
+/// let new = Toml::from_slice(
+///     r#"integer = 42
+/// string = "foo""#,
+/// )
+/// .expect("failed to parse configuration");
+/// assert_eq!(confargs::to_map(old), confargs::to_map(new));
+/// ```
+pub fn to_map(args: impl IntoIterator<Item = String>) -> BTreeMap<String, Vec<String>> {
+    let mut map = BTreeMap::<_, Vec<_>>::new();
+    let mut key = Some(String::new());
+    for arg in args {
+        if arg == "--" {
+            key = None;
+        }
+        let k = match (flag_key(&arg), &key) {
+            (Some(k), Some(_)) => {
+                key = Some(k.to_string());
+                k.to_string()
+            }
+            (_, Some(k)) => k.clone(),
+            (_, None) => String::new(),
+        };
+        map.entry(k).or_default().push(arg);
+    }
+    map
+}
+
+/// Quotes `arg` for POSIX `sh`, if necessary.
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "%+,-./:=@_^".contains(c))
+    {
+        arg.into()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Joins `args` into a single string, quoting each argument for POSIX `sh` as necessary.
+///
+/// This is useful for displaying the command line equivalent to the arguments generated from
+/// configuration.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(
+///     confargs::to_shell_string([
+///         "--string=foo".into(),
+///         "--string=foo bar".into(),
+///         "--string=it's".into(),
+///         "".into(),
+///     ]),
+///     r"--string=foo '--string=foo bar' '--string=it'\''s' ''",
+/// );
+/// ```
+pub fn to_shell_string(args: impl IntoIterator<Item = String>) -> String {
+    args.into_iter()
+        .map(|arg| shell_quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits each `--key=value` argument in `args` into two `--key` and `value` arguments.
+///
+/// Only the first `=` is considered, bare flags and positional arguments are left untouched.
+/// This is useful for consumers expecting values to be specified as separate arguments.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(
+///     confargs::split_eq(
+///         ["--string=foo=bar".into(), "--bool".into(), "positional".into()].into_iter()
+///     )
+///     .collect::<Vec<_>>(),
+///     vec!["--string", "foo=bar", "--bool", "positional"],
+/// );
+/// ```
+pub fn split_eq(args: impl Iterator<Item = String>) -> impl Iterator<Item = String> {
+    args.flat_map(|arg| match flag_key(&arg) {
+        Some(k) if arg.len() > k.len() + 2 => {
+            vec![format!("--{k}"), arg[k.len() + 3..].into()]
+        }
+        _ => vec![arg],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::iter::once;
+    use std::process::Command;
+
+    use clap::Parser;
+    use tempfile::NamedTempFile;
+
+    const TOML_CONFIG: &str = r#"#Test config
+string = "foo"
+integer = 42
+float = 42.2
+true = true
+false = false
+datetime = 01:02:03.000000004
+array = [1, 2, 3]"#;
+
+    #[test]
+    fn args() {
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        assert_eq!(
+            conf.write(TOML_CONFIG.as_bytes())
+                .expect("failed to write config"),
+            TOML_CONFIG.len()
+        );
+
+        const PRINT_ARGS: &str = env!("CARGO_BIN_FILE_PRINT_ARGS");
+        let out = Command::new(PRINT_ARGS)
+            .args([
+                "--test",
+                &format!("@{}", conf.path().display()),
+                "foo",
+                "--bar=baz",
+            ])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(out.stderr).unwrap(), "");
+        assert_eq!(
+            String::from_utf8(out.stdout).unwrap(),
+            format!(
+                r#"{PRINT_ARGS}
+--test
+--array=1
+--array=2
+--array=3
+--datetime=01:02:03.000000004
+--float=42.2
+--integer=42
+--string=foo
+--true
+foo
+--bar=baz
+"#,
+            )
+        );
+    }
+
+    #[test]
+    fn clap() {
+        #[derive(Clone, Debug, Parser, PartialEq)]
+        struct Args {
+            #[clap(long)]
+            string: String,
+            #[clap(long)]
+            integer: isize,
+            #[clap(long)]
+            float: f32,
+            #[clap(long)]
+            r#true: bool,
+            #[clap(long)]
+            r#false: bool,
+            #[clap(long)]
+            datetime: String, // TODO: Use a "time" type
+            #[clap(long)]
+            array: Vec<usize>,
+        }
+
+        #[inline]
+        fn assert_format<T: Format>(buf: impl AsRef<[u8]>) {
+            let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+            let buf = buf.as_ref();
+            assert_eq!(conf.write(buf).expect("failed to write config"), buf.len());
+            assert_eq!(
+                T::read(conf.path())
+                    .map(|args| once("test".into()).chain(args))
+                    .map(Args::try_parse_from)
+                    .unwrap()
+                    .unwrap(),
+                Args {
+                    string: "foo".into(),
+                    integer: 42,
+                    float: 42.2,
+                    r#true: true,
+                    r#false: false,
+                    datetime: "01:02:03.000000004".into(), // TODO: Use a "time" type
+                    array: vec![1, 2, 3],
+                }
+            )
+        }
+
+        assert_format::<Toml>(TOML_CONFIG)
+    }
+
+    #[test]
+    fn clap_table_array() {
+        #[derive(Clone, Debug, Parser, PartialEq)]
+        struct Plugin {
+            #[clap(long)]
+            name: String,
+            #[clap(long)]
+            enabled: bool,
+        }
+
+        let args = Toml::from_slice(
+            r#"plugins = [{ name = "a", enabled = true }, { name = "b" }, { name = "c" }]"#,
+        )
+        .unwrap();
+        let mut plugins = BTreeMap::<usize, Vec<String>>::new();
+        for arg in args {
+            let (i, arg) = arg
+                .strip_prefix("--plugins.")
+                .and_then(|arg| arg.split_once('.'))
+                .expect("argument is not a plugin field");
+            plugins
+                .entry(i.parse().expect("invalid plugin index"))
+                .or_insert_with(|| vec!["plugin".into()])
+                .push(format!("--{arg}"));
+        }
+        assert_eq!(
+            plugins
+                .into_values()
+                .map(Plugin::try_parse_from)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![
+                Plugin {
+                    name: "a".into(),
+                    enabled: true,
+                },
+                Plugin {
+                    name: "b".into(),
+                    enabled: false,
+                },
+                Plugin {
+                    name: "c".into(),
+                    enabled: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn clap_table_array_grouped() {
+        #[derive(Clone, Debug, Parser, PartialEq)]
+        struct Args {
+            #[clap(long = "endpoint-url")]
+            url: Vec<String>,
+            #[clap(long = "endpoint-timeout")]
+            timeout: Vec<u64>,
+        }
+
+        let args = Toml::from_slice_with(
+            r#"[[endpoint]]
+url = "a"
+timeout = 1
+
+[[endpoint]]
+url = "b"
+timeout = 2"#,
+            &Options {
+                table_array: TableArray::Grouped { separator: '-' },
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "--endpoint-timeout=1",
+                "--endpoint-url=a",
+                "--endpoint-timeout=2",
+                "--endpoint-url=b",
+            ]
+        );
+        assert_eq!(
+            Args::try_parse_from(once("test".into()).chain(args)).unwrap(),
+            Args {
+                url: vec!["a".into(), "b".into()],
+                timeout: vec![1, 2],
+            }
+        );
+    }
+
+    #[test]
+    fn clap_array_flag() {
+        #[derive(Clone, Debug, Parser, PartialEq)]
+        struct Repeated {
+            #[clap(long)]
+            array: Vec<String>,
+        }
+
+        #[derive(Clone, Debug, Parser, PartialEq)]
+        struct Once {
+            #[clap(long, multiple_values = true)]
+            array: Vec<String>,
+            #[clap(long)]
+            string: String,
+        }
+
+        const CONFIG: &str = r#"array = ["c", "a", "b"]
+string = "foo""#;
+
+        let args = |array_flag| {
+            Toml::from_slice_with(
+                CONFIG,
+                &Options {
+                    array_flag,
+                    ..Default::default()
+                },
+            )
+            .map(|args| once("test".into()).chain(args).collect::<Vec<_>>())
+            .unwrap()
+        };
+
+        let repeated = args(ArrayFlag::Repeated);
+        assert_eq!(
+            repeated,
+            vec![
+                "test",
+                "--array=c",
+                "--array=a",
+                "--array=b",
+                "--string=foo"
+            ]
+        );
+        assert_eq!(
+            Repeated::try_parse_from(repeated.iter().filter(|arg| !arg.starts_with("--string")))
+                .unwrap(),
+            Repeated {
+                array: vec!["c".into(), "a".into(), "b".into()],
+            }
+        );
+
+        let once = args(ArrayFlag::Once);
+        assert_eq!(once, vec!["test", "--array", "c", "a", "b", "--string=foo"]);
+        assert_eq!(
+            Once::try_parse_from(once).unwrap(),
+            Once {
+                array: vec!["c".into(), "a".into(), "b".into()],
+                string: "foo".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn clap_pair_tables() {
+        fn parse_pair(s: &str) -> Result<(String, String), String> {
+            s.split_once('=')
+                .map(|(k, v)| (k.into(), v.into()))
+                .ok_or_else(|| format!("invalid key-value pair `{s}`"))
+        }
+
+        #[derive(Clone, Debug, Parser, PartialEq)]
+        struct Args {
+            #[clap(long, value_parser = parse_pair)]
+            label: Vec<(String, String)>,
+        }
+
+        let args = Toml::from_slice_with(
+            r#"[labels]
+name = "web"
+tier = "frontend""#,
+            &Options {
+                pair_tables: HashMap::from([("labels".into(), PairTable::new("label"))]),
+                separator: Separator::Space,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            vec!["--label", "name=web", "--label", "tier=frontend"]
+        );
+        assert_eq!(
+            Args::try_parse_from(once("test".into()).chain(args)).unwrap(),
+            Args {
+                label: vec![
+                    ("name".into(), "web".into()),
+                    ("tier".into(), "frontend".into())
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn merge() {
+        #[derive(Clone, Debug, Parser, PartialEq)]
+        struct Args {
+            #[clap(long)]
+            string: String,
+            #[clap(long)]
+            integer: isize,
+            #[clap(long)]
+            array: Vec<usize>,
+        }
+
+        const CONFIG: &str = r#"string = "foo"
+integer = 42
+array = [1, 2, 3]"#;
+
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        assert_eq!(
+            conf.write(CONFIG.as_bytes())
+                .expect("failed to write config"),
+            CONFIG.len()
+        );
+
+        let (config, cli): (Vec<_>, Vec<_>) = [
+            format!("@{}", conf.path().display()),
+            "--string=override".into(),
+            "--array=4".into(),
+        ]
+        .into_iter()
+        .partition(|arg| prefix_char_filter::<'@'>(arg).is_some());
+        let config = args_from::<Toml>(prefix_char_filter::<'@'>, config.into_iter())
+            .expect("failed to parse config");
+        assert_eq!(
+            Args::try_parse_from(once("test".into()).chain(super::merge(config, cli))).unwrap(),
+            Args {
+                string: "override".into(),
+                integer: 42,
+                array: vec![4],
+            }
+        );
+    }
+
+    #[test]
+    fn merge_separate() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let opts = Options {
+            separator: Separator::Space,
+            trailing_key: Some("trailing".into()),
+            ..Default::default()
+        };
+        let config = Toml::from_slice_with(
+            r#"name = "foo"
+verbose = true
+array = ["a", "b"]
+trailing = ["-x"]"#,
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            args(&[
+                "--array",
+                "a",
+                "--array",
+                "b",
+                "--name",
+                "foo",
+                "--verbose",
+                "--",
+                "-x"
+            ])
+        );
+        assert_eq!(
+            super::merge(config.clone(), args(&["--name", "bar", "--array=c"])),
+            args(&["--verbose", "--name", "bar", "--array=c", "--", "-x"])
+        );
+        assert_eq!(
+            super::merge(config, args(&["--", "--name", "-y"])),
+            args(&[
+                "--array",
+                "a",
+                "--array",
+                "b",
+                "--name",
+                "foo",
+                "--verbose",
+                "--",
+                "-x",
+                "--name",
+                "-y"
+            ])
+        );
+
+        let config = Toml::from_slice_with(
+            r#"array = ["a", "b"]
+name = "foo""#,
+            &Options {
+                array_flag: ArrayFlag::Once,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            super::merge(config, args(&["--array=c", "pos"])),
+            args(&["--name=foo", "--array=c", "pos"])
+        );
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn gzip() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        let conf = tempfile::Builder::new()
+            .suffix(".toml.gz")
+            .tempfile()
+            .expect("failed to create temporary file");
+        let mut enc = GzEncoder::new(conf.as_file(), Compression::default());
+        enc.write_all(r#"string = "foo""#.as_bytes())
+            .expect("failed to write config");
+        let _ = enc.finish().expect("failed to compress config");
+        assert_eq!(Toml::read(conf.path()).unwrap(), vec!["--string=foo"]);
+
+        let conf = tempfile::Builder::new()
+            .suffix(".toml.gz")
+            .tempfile()
+            .expect("failed to create temporary file");
+        conf.as_file()
+            .write_all(r#"string = "foo""#.as_bytes())
+            .expect("failed to write config");
+        assert!(Toml::read(conf.path())
+            .unwrap_err()
+            .to_string()
+            .starts_with("failed to decompress"));
+    }
+
+    #[test]
+    fn to_shell_string() {
+        assert_eq!(super::to_shell_string([]), "");
+        assert_eq!(super::to_shell_string(["".into()]), "''");
+        assert_eq!(
+            super::to_shell_string(["--key=foo".into(), "positional".into()]),
+            "--key=foo positional"
+        );
+        assert_eq!(
+            super::to_shell_string(["--key=foo bar".into()]),
+            "'--key=foo bar'"
+        );
+        assert_eq!(
+            super::to_shell_string(["--key='foo'".into()]),
+            r"'--key='\''foo'\'''"
+        );
+        assert_eq!(
+            super::to_shell_string([r#"--key="$foo" `bar` \baz"#.into()]),
+            r#"'--key="$foo" `bar` \baz'"#
+        );
+        assert_eq!(
+            super::to_shell_string(["--key=a\nb".into(), "*".into()]),
+            "'--key=a\nb' '*'"
+        );
+    }
+
+    #[test]
+    fn args_multi() {
+        let mut foo = NamedTempFile::new().expect("failed to create temporary file");
+        foo.write_all(r#"string = "foo""#.as_bytes())
+            .expect("failed to write config");
+        let mut bar = NamedTempFile::new().expect("failed to create temporary file");
+        bar.write_all("integer = 42".as_bytes())
+            .expect("failed to write config");
+
+        assert_eq!(
+            args_from_multi_with::<Toml>(
+                |arg| {
+                    arg.strip_prefix('@')
+                        .map(|paths| paths.split(',').map(PathBuf::from).collect())
+                },
+                [
+                    "test".into(),
+                    format!("@{},{}", foo.path().display(), bar.path().display()),
+                    "--bar=baz".into(),
+                ]
+                .into_iter(),
+                &Options::default(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec!["test", "--string=foo", "--integer=42", "--bar=baz"]
+        );
+    }
+
+    #[test]
+    fn args_interleaved() {
+        let mut foo = NamedTempFile::new().expect("failed to create temporary file");
+        foo.write_all("f1 = 1\nf1b = true".as_bytes())
+            .expect("failed to write config");
+        let mut bar = NamedTempFile::new().expect("failed to create temporary file");
+        bar.write_all("f2 = 2".as_bytes())
+            .expect("failed to write config");
+
+        assert_eq!(
+            args_from::<Toml>(
+                prefix_char_filter::<'@'>,
+                [
+                    "a".into(),
+                    format!("@{}", foo.path().display()),
+                    "b".into(),
+                    format!("@{}", bar.path().display()),
+                    "c".into(),
+                ]
+                .into_iter(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec!["a", "--f1=1", "--f1b", "b", "--f2=2", "c"]
+        );
     }
 
     #[test]
-    fn clap() {
+    fn escape() {
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        conf.write_all(r#"string = "foo""#.as_bytes())
+            .expect("failed to write config");
+
+        assert_eq!(
+            args_from::<Toml>(
+                prefix_char_filter::<'@'>,
+                [
+                    "@@literal".into(),
+                    format!("@{}", conf.path().display()),
+                    "@@@triple".into(),
+                    "--@@flag".into(),
+                ]
+                .into_iter(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec!["@literal", "--string=foo", "@@triple", "--@@flag"]
+        );
+    }
+
+    #[test]
+    fn prefix_char_filter_runtime() {
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        conf.write_all(r#"string = "foo""#.as_bytes())
+            .expect("failed to write config");
+
+        let prefix = "%".parse().unwrap();
+        assert_eq!(
+            args_from::<Toml>(
+                super::prefix_char_filter_runtime(prefix),
+                [
+                    format!("%{}", conf.path().display()),
+                    "@foo".into(),
+                    "%%bar".into(),
+                ]
+                .into_iter(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec!["--string=foo", "@foo", "%bar"]
+        );
+    }
+
+    #[test]
+    fn to_map() {
+        assert_eq!(
+            super::to_map([
+                "pos".into(),
+                "--b=1".into(),
+                "--a".into(),
+                "--b=2".into(),
+                "--sep".into(),
+                "value".into(),
+                "--".into(),
+                "--trailing".into(),
+            ]),
+            BTreeMap::from([
+                (
+                    "".into(),
+                    vec!["pos".into(), "--".into(), "--trailing".into()]
+                ),
+                ("a".into(), vec!["--a".into()]),
+                ("b".into(), vec!["--b=1".into(), "--b=2".into()]),
+                ("sep".into(), vec!["--sep".into(), "value".into()]),
+            ])
+        );
+        assert_ne!(
+            super::to_map(["--b=1".into(), "--b=2".into()]),
+            super::to_map(["--b=2".into(), "--b=1".into()])
+        );
+    }
+
+    #[test]
+    fn format_directive() {
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        conf.write_all("#!confargs toml\nstring = \"foo\"".as_bytes())
+            .expect("failed to write config");
+        let mut unknown = NamedTempFile::new().expect("failed to create temporary file");
+        unknown
+            .write_all("#!confargs yaml\nstring: foo".as_bytes())
+            .expect("failed to write config");
+
+        let opts = Options {
+            format_directive: true,
+            ..Default::default()
+        };
+        let args = |path: &Path, opts: &Options| {
+            args_from_auto_with(
+                prefix_char_filter::<'@'>,
+                ["test".into(), format!("@{}", path.display())].into_iter(),
+                opts,
+            )
+            .map(|args| args.into_iter().collect::<Vec<_>>())
+        };
+        assert_eq!(
+            args(conf.path(), &opts).unwrap(),
+            vec!["test", "--string=foo"]
+        );
+        assert!(args(conf.path(), &Options::default()).is_err());
+        assert!(args(unknown.path(), &opts)
+            .unwrap_err()
+            .to_string()
+            .contains("unsupported config file format `yaml`"));
+    }
+
+    #[test]
+    fn prefix_char_filter_bare() {
+        assert_eq!(prefix_char_filter::<'@'>("@"), None);
+        assert_eq!(
+            args_from::<Toml>(
+                prefix_char_filter::<'@'>,
+                ["test".into(), "@".into(), "@@".into(), "@@foo".into()].into_iter(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec!["test", "@", "@@", "@foo"]
+        );
+    }
+
+    #[test]
+    fn args_from_try_with() {
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        conf.write_all(r#"string = "foo""#.as_bytes())
+            .expect("failed to write config");
+
+        assert_eq!(
+            super::args_from_try_with::<Toml>(
+                strict_prefix_char_filter::<'@'>,
+                [
+                    "test".into(),
+                    format!("@{}", conf.path().display()),
+                    "@@".into(),
+                    "@@foo".into(),
+                ]
+                .into_iter(),
+                &Options::default(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec!["test", "--string=foo", "@@", "@foo"]
+        );
+        let err = super::args_from_try_with::<Toml>(
+            strict_prefix_char_filter::<'@'>,
+            ["test".into(), "@".into(), "foo".into()].into_iter(),
+            &Options::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "missing configuration file path after `@`");
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn try_formats() {
+        let formats: &[&dyn DynFormat] = &[&Toml, &Json];
+        assert_eq!(
+            super::try_formats(formats, br#"{"string": "foo"}"#).unwrap(),
+            vec!["--string=foo"]
+        );
+        assert_eq!(
+            super::try_formats(formats, br#"string = "foo""#).unwrap(),
+            vec!["--string=foo"]
+        );
+        let err = super::try_formats(formats, b"string: foo").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = err.to_string();
+        assert!(err.starts_with("failed to parse configuration using any format: `toml`: "));
+        assert!(err.contains(", `json`: failed to parse JSON: "));
+        assert!(super::try_formats(&[], b"").is_err());
+    }
+
+    #[test]
+    fn args_len() {
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        conf.write_all(b"array = [1, 2, 3]\nstring = \"foo\"")
+            .expect("failed to write config");
+
+        let mut args = args_from::<Toml>(
+            prefix_char_filter::<'@'>,
+            ["test".into(), format!("@{}", conf.path().display())].into_iter(),
+        )
+        .unwrap();
+        assert_eq!(args.len(), 5);
+        assert_eq!(args.next_back().as_deref(), Some("--string=foo"));
+        assert_eq!(args.len(), 4);
+        assert_eq!(args.collect::<Vec<_>>().len(), 4);
+    }
+
+    #[test]
+    fn args_auto() {
+        let conf = tempfile::Builder::new()
+            .suffix(".toml")
+            .tempfile()
+            .expect("failed to create temporary file");
+        conf.as_file()
+            .write_all(r#"string = "foo""#.as_bytes())
+            .expect("failed to write config");
+        let unknown = tempfile::Builder::new()
+            .suffix(".unknown")
+            .tempfile()
+            .expect("failed to create temporary file");
+
+        assert_eq!(
+            args_from_auto_with(
+                prefix_char_filter::<'@'>,
+                [
+                    "test".into(),
+                    format!("@{}", conf.path().display()),
+                    "--bar=baz".into()
+                ]
+                .into_iter(),
+                &Options::default(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec!["test", "--string=foo", "--bar=baz"]
+        );
+        assert!(args_from_auto_with(
+            prefix_char_filter::<'@'>,
+            [format!("@{}", unknown.path().display())].into_iter(),
+            &Options::default(),
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn args_auto_mixed() {
+        let toml = tempfile::Builder::new()
+            .suffix(".toml")
+            .tempfile()
+            .expect("failed to create temporary file");
+        toml.as_file()
+            .write_all(r#"string = "foo""#.as_bytes())
+            .expect("failed to write config");
+        let json = tempfile::Builder::new()
+            .suffix(".json")
+            .tempfile()
+            .expect("failed to create temporary file");
+        json.as_file()
+            .write_all(r#"{ "integer": 42 }"#.as_bytes())
+            .expect("failed to write config");
+
+        assert_eq!(
+            args_from_auto_with(
+                prefix_char_filter::<'@'>,
+                [
+                    "test".into(),
+                    format!("@{}", toml.path().display()),
+                    format!("@{}", json.path().display()),
+                ]
+                .into_iter(),
+                &Options::default(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec!["test", "--string=foo", "--integer=42"]
+        );
+    }
+
+    #[test]
+    fn skip_missing() {
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let missing = dir.path().join("missing.toml");
+        let invalid = dir.path().join("invalid.toml");
+        fs::write(&invalid, "string = ").expect("failed to write config");
+
+        let args = |path: &Path, skip_missing| {
+            args_from_with::<Toml>(
+                prefix_char_filter::<'@'>,
+                ["test".into(), format!("@{}", path.display())].into_iter(),
+                &Options {
+                    skip_missing,
+                    ..Default::default()
+                },
+            )
+            .map(|args| args.into_iter().collect::<Vec<_>>())
+        };
+        assert!(args(&missing, false).is_err());
+        assert_eq!(args(&missing, true).unwrap(), vec!["test"]);
+        assert!(args(&invalid, true).is_err());
+    }
+
+    #[test]
+    fn split_eq() {
+        assert_eq!(
+            super::split_eq(
+                [
+                    "--string=foo",
+                    "--empty=",
+                    "--eq=a=b",
+                    "--bool",
+                    "-s",
+                    "positional=value",
+                    "--",
+                ]
+                .into_iter()
+                .map(Into::into)
+            )
+            .collect::<Vec<_>>(),
+            vec![
+                "--string",
+                "foo",
+                "--empty",
+                "",
+                "--eq",
+                "a=b",
+                "--bool",
+                "-s",
+                "positional=value",
+                "--",
+            ]
+        );
+    }
+
+    #[test]
+    fn args_with_count() {
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let a = dir.path().join("a.toml");
+        let b = dir.path().join("b.toml");
+        let missing = dir.path().join("missing.toml");
+        fs::write(&a, "string = \"a\"").expect("failed to write config");
+        fs::write(&b, "integer = 42").expect("failed to write config");
+
+        let (args, count) = args_from_with_count::<Toml>(
+            prefix_char_filter::<'@'>,
+            [
+                "test".into(),
+                format!("@{}", a.display()),
+                "--bool".into(),
+                format!("@{}", missing.display()),
+                format!("@{}", b.display()),
+            ]
+            .into_iter(),
+            &Options {
+                skip_missing: true,
+                ..Default::default()
+            },
+        )
+        .expect("failed to parse config");
+        assert_eq!(
+            args.into_iter().collect::<Vec<_>>(),
+            vec!["test", "--string=a", "--bool", "--integer=42"]
+        );
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn args_from_env_var() {
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let env = dir.path().join("env.toml");
+        let cli = dir.path().join("cli.toml");
+        fs::write(&env, "string = \"env\"").expect("failed to write config");
+        fs::write(&cli, "integer = 42").expect("failed to write config");
+
+        let args = |var| {
+            args_from_env_var_with::<Toml>(
+                var,
+                prefix_char_filter::<'@'>,
+                [
+                    "test".into(),
+                    "--bool".into(),
+                    format!("@{}", cli.display()),
+                ]
+                .into_iter(),
+                &Options::default(),
+            )
+            .map(|args| args.into_iter().collect::<Vec<_>>())
+        };
+
+        env::set_var("CONFARGS_TEST_ENV_VAR_SET", &env);
+        env::set_var(
+            "CONFARGS_TEST_ENV_VAR_MISSING",
+            dir.path().join("missing.toml"),
+        );
+        env::remove_var("CONFARGS_TEST_ENV_VAR_UNSET");
+        assert_eq!(
+            args("CONFARGS_TEST_ENV_VAR_SET").unwrap(),
+            vec!["test", "--string=env", "--bool", "--integer=42"]
+        );
+        assert_eq!(
+            args("CONFARGS_TEST_ENV_VAR_UNSET").unwrap(),
+            vec!["test", "--bool", "--integer=42"]
+        );
+        assert_eq!(
+            args("CONFARGS_TEST_ENV_VAR_MISSING").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn args_closure_filter() {
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "integer = 42").expect("failed to write config");
+
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap();
+        let prefix = format!("--{stem}=");
+        assert_eq!(
+            args_from::<Toml>(
+                |arg| arg.strip_prefix(prefix.as_str()).map(Path::new),
+                [
+                    "test".into(),
+                    format!("{prefix}{}", path.display()),
+                    "--bool".into(),
+                ]
+                .into_iter(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec!["test", "--integer=42", "--bool"]
+        );
+    }
+
+    #[cfg(all(feature = "directories", target_os = "linux"))]
+    #[test]
+    fn args_from_config_dir() {
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        fs::create_dir(dir.path().join("mytool")).expect("failed to create directory");
+        fs::write(
+            dir.path().join("mytool").join("config.toml"),
+            "integer = 42",
+        )
+        .expect("failed to write config");
+        env::set_var("XDG_CONFIG_HOME", dir.path());
+
+        assert_eq!(
+            config_dir_path("mytool", "config.toml"),
+            Some(dir.path().join("mytool").join("config.toml"))
+        );
+        let args = |file| {
+            args_from_config_dir_with::<Toml>(
+                "mytool",
+                file,
+                prefix_char_filter::<'@'>,
+                ["test".into(), "--bool".into()].into_iter(),
+                &Options::default(),
+            )
+            .map(|args| args.into_iter().collect::<Vec<_>>())
+        };
+        assert_eq!(
+            args("config.toml").unwrap(),
+            vec!["test", "--integer=42", "--bool"]
+        );
+        assert_eq!(args("missing.toml").unwrap(), vec!["test", "--bool"]);
+    }
+
+    #[test]
+    fn args_from_exe_dir() {
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let exe = dir.path().join("bin").join("mytool");
+        fs::create_dir(dir.path().join("bin")).expect("failed to create directory");
+        fs::write(dir.path().join("bin").join("config.toml"), "integer = 42")
+            .expect("failed to write config");
+        fs::write(dir.path().join("bin").join("invalid.toml"), "integer =")
+            .expect("failed to write config");
+
+        assert_eq!(
+            exe_relative_path(&exe, "config.toml"),
+            dir.path().join("bin").join("config.toml")
+        );
+        let args = |file| {
+            args_from_path_with::<Toml>(
+                Some(exe_relative_path(&exe, file)),
+                prefix_char_filter::<'@'>,
+                ["test".into(), "--bool".into()].into_iter(),
+                &Options::default(),
+            )
+            .map(Iterator::collect::<Vec<_>>)
+        };
+        assert_eq!(
+            args("config.toml").unwrap(),
+            vec!["test", "--integer=42", "--bool"]
+        );
+        assert_eq!(args("missing.toml").unwrap(), vec!["test", "--bool"]);
+        assert!(args("invalid.toml").is_err());
+        assert_eq!(
+            exe_dir_path("config.toml").unwrap().parent(),
+            env::current_exe().unwrap().parent()
+        );
+    }
+
+    #[test]
+    fn args_with_provenance() {
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let a = dir.path().join("a.toml");
+        let b = dir.path().join("b.toml");
+        fs::write(&a, "string = \"a\"\ninteger = 1").expect("failed to write config");
+        fs::write(&b, "string = \"b\"").expect("failed to write config");
+
+        assert_eq!(
+            args_from_with_provenance::<Toml>(
+                prefix_char_filter::<'@'>,
+                [
+                    "test".into(),
+                    format!("@{}", a.display()),
+                    format!("@{}", b.display()),
+                    "--string=cli".into(),
+                ]
+                .into_iter(),
+                &Options::default(),
+            )
+            .unwrap(),
+            vec![
+                ("test".into(), Source::Cli),
+                ("--integer=1".into(), Source::Config(a.clone())),
+                ("--string=a".into(), Source::Config(a)),
+                ("--string=b".into(), Source::Config(b)),
+                ("--string=cli".into(), Source::Cli),
+            ]
+        );
+    }
+
+    #[test]
+    fn args_from_embedded_with() {
         #[derive(Clone, Debug, Parser, PartialEq)]
         struct Args {
             #[clap(long)]
             string: String,
             #[clap(long)]
-            integer: isize,
-            #[clap(long)]
-            float: f32,
-            #[clap(long)]
-            r#true: bool,
+            integer: u8,
             #[clap(long)]
-            r#false: bool,
+            bool: bool,
+        }
+
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        conf.write_all(r#"string = "file""#.as_bytes())
+            .expect("failed to write config");
+
+        const EMBEDDED: &[u8] = br#"string = "embedded"
+integer = 1"#;
+        let args = super::args_from_embedded_with::<Toml>(
+            prefix_char_filter::<'@'>,
+            EMBEDDED,
+            [
+                "test".into(),
+                format!("@{}", conf.path().display()),
+                "--bool".into(),
+            ]
+            .into_iter(),
+            &Options::default(),
+        )
+        .unwrap()
+        .collect::<Vec<_>>();
+        assert_eq!(args, vec!["test", "--integer=1", "--string=file", "--bool"]);
+        assert_eq!(
+            Args::try_parse_from(args).unwrap(),
+            Args {
+                string: "file".into(),
+                integer: 1,
+                bool: true,
+            }
+        );
+
+        let args = super::args_from_embedded_with::<Toml>(
+            prefix_char_filter::<'@'>,
+            EMBEDDED,
+            [
+                "test".into(),
+                format!("@{}", conf.path().display()),
+                "--bool".into(),
+            ]
+            .into_iter(),
+            &Options {
+                separator: Separator::Space,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .collect::<Vec<_>>();
+        assert_eq!(
+            args,
+            vec!["test", "--integer", "1", "--string", "file", "--bool"]
+        );
+        assert_eq!(
+            Args::try_parse_from(args).unwrap(),
+            Args {
+                string: "file".into(),
+                integer: 1,
+                bool: true,
+            }
+        );
+
+        let err = super::args_from_embedded_with::<Toml>(
+            prefix_char_filter::<'@'>,
+            b"string = ",
+            once("test".into()),
+            &Options::default(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Failed to parse embedded config: "));
+    }
+
+    #[test]
+    fn args_from_expanded() {
+        #[derive(Clone, Debug, Parser, PartialEq)]
+        struct Args {
             #[clap(long)]
-            datetime: String, // TODO: Use a "time" type
+            string: Vec<String>,
             #[clap(long)]
-            array: Vec<usize>,
+            integer: u8,
         }
 
-        #[inline]
-        fn assert_format<T: Format>(buf: impl AsRef<[u8]>) {
-            let mut conf = NamedTempFile::new().expect("failed to create temporary file");
-            let buf = buf.as_ref();
-            assert_eq!(conf.write(buf).expect("failed to write config"), buf.len());
-            assert_eq!(
-                T::read(conf.path())
-                    .map(|args| once("test".into()).chain(args))
-                    .map(Args::try_parse_from)
-                    .unwrap()
-                    .unwrap(),
-                Args {
-                    string: "foo".into(),
-                    integer: 42,
-                    float: 42.2,
-                    r#true: true,
-                    r#false: false,
-                    datetime: "01:02:03.000000004".into(), // TODO: Use a "time" type
-                    array: vec![1, 2, 3],
-                }
-            )
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let a = dir.path().join("a.toml");
+        let b = dir.path().join("b.toml");
+        fs::write(&a, "string = \"a\"\ninteger = 1").expect("failed to write config");
+        fs::write(&b, "").expect("failed to write config");
+
+        let args = || {
+            [
+                "test".into(),
+                format!("@{}", a.display()),
+                format!("@{}", b.display()),
+                "--string=cli".into(),
+            ]
+            .into_iter()
+        };
+        let expansion = super::args_from_expanded::<Toml>(
+            prefix_char_filter::<'@'>,
+            args(),
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            expansion.args,
+            ["test", "--integer=1", "--string=a", "--string=cli"]
+        );
+        assert_eq!(expansion.sources, [a.clone(), b.clone()]);
+        assert_eq!(
+            Args::try_parse_from(expansion.args()).unwrap(),
+            Args::try_parse_from(args_from::<Toml>(prefix_char_filter::<'@'>, args()).unwrap())
+                .unwrap()
+        );
+        assert_eq!(
+            Args::try_parse_from(super::Args::from(expansion.clone())).unwrap(),
+            Args::try_parse_from(expansion.args()).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_empty() {
+        let conf = NamedTempFile::new().expect("failed to create temporary file");
+        assert_eq!(Toml::read(conf.path()).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn args_auto_formats() {
+        /// Format, which renders each non-empty line as an argument.
+        struct Lines;
+
+        impl Format for Lines {
+            type IntoIter = Vec<String>;
+
+            fn from_slice(buf: impl AsRef<[u8]>) -> io::Result<Self::IntoIter> {
+                Ok(String::from_utf8_lossy(buf.as_ref())
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(Into::into)
+                    .collect())
+            }
+
+            fn extensions() -> &'static [&'static str] {
+                &["lines", "txt"]
+            }
         }
 
-        assert_format::<Toml>(TOML_CONFIG)
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let toml = dir.path().join("config.toml");
+        let lines = dir.path().join("config.txt");
+        let unknown = dir.path().join("config.unknown");
+        fs::write(&toml, "integer = 42").expect("failed to write config");
+        fs::write(&lines, "--string=foo\n\n--bool\n").expect("failed to write config");
+        fs::write(&unknown, "").expect("failed to write config");
+
+        let args = |path: &Path| {
+            args_from_auto_formats_with(
+                prefix_char_filter::<'@'>,
+                [
+                    "test".into(),
+                    format!("@{}", toml.display()),
+                    format!("@{}", path.display()),
+                ]
+                .into_iter(),
+                &Options::default(),
+                &[&Toml, &Lines],
+            )
+            .map(|args| args.into_iter().collect::<Vec<_>>())
+        };
+        assert_eq!(
+            args(&lines).unwrap(),
+            vec!["test", "--integer=42", "--string=foo", "--bool"]
+        );
+        assert!(args(&unknown)
+            .unwrap_err()
+            .to_string()
+            .ends_with("supported extensions are: `.toml`, `.lines`, `.txt`"));
+    }
+
+    #[test]
+    fn dedup_paths() {
+        let dir = tempfile::tempdir().expect("failed to create temporary directory");
+        let a = dir.path().join("a.toml");
+        let b = dir.path().join("b.toml");
+        fs::write(&a, "string = \"a\"").expect("failed to write config");
+        fs::write(&b, "integer = 42").expect("failed to write config");
+
+        let args = |dedup_paths| {
+            args_from_with::<Toml>(
+                prefix_char_filter::<'@'>,
+                [
+                    "test".into(),
+                    format!("@{}", a.display()),
+                    format!("@{}", b.display()),
+                    format!("@{}", dir.path().join(".").join("a.toml").display()),
+                    format!("@{}", a.display()),
+                ]
+                .into_iter(),
+                &Options {
+                    dedup_paths,
+                    ..Default::default()
+                },
+            )
+            .map(|args| args.into_iter().collect::<Vec<_>>())
+        };
+        assert_eq!(
+            args(false).unwrap(),
+            vec![
+                "test",
+                "--string=a",
+                "--integer=42",
+                "--string=a",
+                "--string=a"
+            ]
+        );
+        assert_eq!(
+            args(true).unwrap(),
+            vec!["test", "--string=a", "--integer=42"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_fd() {
+        use std::os::unix::io::AsRawFd;
+
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        conf.write_all(r#"string = "fd""#.as_bytes())
+            .expect("failed to write config");
+        let fd = conf.as_file().as_raw_fd();
+        assert_eq!(
+            args_from::<Toml>(
+                prefix_char_filter::<'@'>,
+                ["test".into(), format!("@fd:{fd}")].into_iter(),
+            )
+            .unwrap()
+            .collect::<Vec<_>>(),
+            vec!["test", "--string=fd"]
+        );
+        assert!(Toml::read("fd:999999").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_pipe() {
+        use std::os::unix::io::AsRawFd;
+
+        let (r, mut w) = std::io::pipe().expect("failed to create pipe");
+        w.write_all(r#"string = "pipe""#.as_bytes())
+            .expect("failed to write config");
+        drop(w);
+        assert_eq!(
+            Toml::read(format!("/dev/fd/{}", r.as_raw_fd())).unwrap(),
+            vec!["--string=pipe"]
+        );
+    }
+
+    #[test]
+    fn args_from_vec() {
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        conf.write_all(r#"string = "foo""#.as_bytes())
+            .expect("failed to write config");
+        let args: Vec<String> = super::args_from_vec::<Toml>(
+            prefix_char_filter::<'@'>,
+            ["test".into(), format!("@{}", conf.path().display())].into_iter(),
+        )
+        .unwrap();
+        assert_eq!(args, vec!["test", "--string=foo"]);
+    }
+
+    #[test]
+    fn root_flag() {
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        conf.write_all(
+            r#"[dev]
+host = "localhost"
+
+[prod]
+host = "example.com""#
+                .as_bytes(),
+        )
+        .expect("failed to write config");
+
+        let args = |args: &[&str], root: Option<&str>| {
+            args_from_with::<Toml>(
+                prefix_char_filter::<'@'>,
+                once("test".into())
+                    .chain(once(format!("@{}", conf.path().display())))
+                    .chain(args.iter().map(|arg| arg.to_string()))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+                &Options {
+                    root: root.map(Into::into),
+                    root_flag: Some("config-section".into()),
+                    ..Default::default()
+                },
+            )
+            .map(|args| args.into_iter().collect::<Vec<_>>())
+        };
+        assert_eq!(
+            args(&["--config-section=prod", "--bool"], None).unwrap(),
+            vec!["test", "--host=example.com", "--bool"]
+        );
+        assert_eq!(
+            args(&["--config-section=prod"], Some("dev")).unwrap(),
+            vec!["test", "--host=example.com"]
+        );
+        assert_eq!(
+            args(&[], Some("dev")).unwrap(),
+            vec!["test", "--host=localhost"]
+        );
+        assert_eq!(
+            args(&["--config-section="], Some("dev")).unwrap(),
+            vec!["test", "--dev.host=localhost", "--prod.host=example.com"]
+        );
+        assert!(args(&["--config-section=staging"], None).is_err());
+    }
+
+    #[test]
+    fn profile_flag() {
+        let mut conf = NamedTempFile::new().expect("failed to create temporary file");
+        conf.write_all(
+            r#"[profile.dev]
+debug = true
+
+[profile.release]
+opt-level = 3"#
+                .as_bytes(),
+        )
+        .expect("failed to write config");
+
+        let args = |args: &[&str], profile: Option<&str>| {
+            args_from_with::<Toml>(
+                prefix_char_filter::<'@'>,
+                once("test".into())
+                    .chain(once(format!("@{}", conf.path().display())))
+                    .chain(args.iter().map(|arg| arg.to_string()))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+                &Options {
+                    profile: profile.map(Into::into),
+                    profile_flag: Some("profile".into()),
+                    ..Default::default()
+                },
+            )
+            .map(Iterator::collect::<Vec<_>>)
+        };
+        assert_eq!(
+            args(&["--profile=release"], Some("dev")).unwrap(),
+            vec!["test", "--opt-level=3"]
+        );
+        assert_eq!(args(&[], Some("dev")).unwrap(), vec!["test", "--debug"]);
+        assert!(args(&["--profile=bench"], None).is_err());
     }
 }
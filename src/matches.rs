@@ -0,0 +1,166 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::merge_by;
+
+use std::collections::HashSet;
+use std::iter::once;
+
+use clap::{ArgMatches, Command, ErrorKind, ValueSource};
+
+/// Merges arguments parsed from configuration files with command-line arguments `cli`, which
+/// were already parsed by `cmd` into `matches`, such that configuration only supplies arguments
+/// not explicitly specified on the command line.
+///
+/// Unlike [`merge`](crate::merge), arguments are matched using `cmd` definitions, therefore
+/// command-line arguments specified using short flags or aliases take precedence as well. All
+/// `config` flags, for which the matching argument was explicitly specified on the command line
+/// are dropped. Same as for [`merge`](crate::merge), separate values of dropped flags are
+/// dropped along with them and `config` arguments following `--` are moved after all flags.
+/// The remaining `config` arguments are followed by `cli` arguments. Note, that `cli` should
+/// not contain the binary name, since it is not the first argument in the returned [`Vec`].
+///
+/// # Examples
+///
+/// ```
+/// use clap::{Arg, Command};
+/// use confargs::{Format, Toml};
+///
+/// let cmd = Command::new("test")
+///     .arg(Arg::new("port").long("port").short('p').takes_value(true))
+///     .arg(Arg::new("host").long("host").takes_value(true));
+/// let matches = cmd.clone().get_matches_from(["test", "-p", "8080"]);
+/// let config = Toml::from_slice(
+///     r#"host = "localhost"
+/// port = 80"#,
+/// )
+/// .expect("failed to parse configuration");
+/// assert_eq!(
+///     confargs::merge_matches(&cmd, &matches, config, ["-p".into(), "8080".into()]),
+///     vec!["--host=localhost", "-p", "8080"],
+/// );
+/// ```
+pub fn merge_matches(
+    cmd: &Command<'_>,
+    matches: &ArgMatches,
+    config: impl IntoIterator<Item = String>,
+    cli: impl IntoIterator<Item = String>,
+) -> Vec<String> {
+    let keys: HashSet<_> = cmd
+        .get_arguments()
+        .filter(|arg| {
+            matches.try_contains_id(arg.get_id()).unwrap_or_default()
+                && matches.value_source(arg.get_id()) == Some(ValueSource::CommandLine)
+        })
+        .flat_map(|arg| {
+            arg.get_long()
+                .into_iter()
+                .chain(arg.get_all_aliases().unwrap_or_default())
+        })
+        .collect();
+    merge_by(config, cli.into_iter().collect(), |k| keys.contains(k))
+}
+
+/// Validates arguments parsed from configuration files `args` against `cmd` without
+/// constructing the final parsed value.
+///
+/// The returned [`clap::Error`] describes the offending argument and includes suggestions
+/// where available. Since configuration usually specifies a subset of arguments, missing
+/// required arguments and subcommands are not considered errors. Note, that `args` should not
+/// contain the binary name.
+///
+/// # Examples
+///
+/// ```
+/// use clap::{Arg, Command};
+/// use confargs::{Format, Toml};
+///
+/// let cmd = Command::new("test")
+///     .arg(Arg::new("port").long("port").takes_value(true).required(true))
+///     .arg(Arg::new("host").long("host").takes_value(true));
+/// let config = Toml::from_slice(r#"host = "localhost""#).expect("failed to parse configuration");
+/// assert!(confargs::validate_against(&cmd, &config).is_ok());
+///
+/// let config = Toml::from_slice(r#"hots = "localhost""#).expect("failed to parse configuration");
+/// let err = confargs::validate_against(&cmd, &config).unwrap_err();
+/// assert_eq!(err.kind(), clap::ErrorKind::UnknownArgument);
+/// ```
+pub fn validate_against(cmd: &Command<'_>, args: &[String]) -> clap::Result<()> {
+    match cmd
+        .clone()
+        .try_get_matches_from(once(cmd.get_name()).chain(args.iter().map(String::as_str)))
+    {
+        Ok(_) => Ok(()),
+        Err(e)
+            if matches!(
+                e.kind(),
+                ErrorKind::MissingRequiredArgument | ErrorKind::MissingSubcommand
+            ) =>
+        {
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::Arg;
+
+    #[test]
+    fn merge_matches() {
+        let cmd = Command::new("test")
+            .arg(Arg::new("verbose").long("verbose").short('v').alias("loud"))
+            .arg(
+                Arg::new("level")
+                    .long("level")
+                    .takes_value(true)
+                    .default_value("1"),
+            )
+            .arg(Arg::new("name").long("name").takes_value(true));
+        let cli = ["-v".to_string(), "--name=cli".into()];
+        let matches = cmd
+            .clone()
+            .get_matches_from(["test".to_string()].iter().chain(&cli));
+        assert_eq!(
+            super::merge_matches(
+                &cmd,
+                &matches,
+                [
+                    "--loud".into(),
+                    "--verbose".into(),
+                    "--level=2".into(),
+                    "--name=conf".into(),
+                ],
+                cli,
+            ),
+            vec!["--level=2", "-v", "--name=cli"]
+        );
+    }
+
+    #[test]
+    fn validate_against() {
+        let cmd = Command::new("test")
+            .arg(
+                Arg::new("level")
+                    .long("level")
+                    .takes_value(true)
+                    .required(true)
+                    .possible_values(["low", "high"]),
+            )
+            .arg(Arg::new("verbose").long("verbose"));
+        assert!(super::validate_against(&cmd, &[]).is_ok());
+        assert!(super::validate_against(&cmd, &["--verbose".into()]).is_ok());
+        assert!(super::validate_against(&cmd, &["--level=low".into()]).is_ok());
+
+        let err = super::validate_against(&cmd, &["--verbos".into()]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnknownArgument);
+        let msg = err.to_string();
+        assert!(msg.contains("--verbos"), "{msg}");
+        assert!(msg.contains("--verbose"), "{msg}");
+
+        let err = super::validate_against(&cmd, &["--level=medium".into()]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    }
+}
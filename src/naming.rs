@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt;
+
+/// Strategy mapping flattened configuration keys, e.g. `server.max_retries`, to flag names,
+/// i.e. the part of the argument following the `--`.
+///
+/// Per-key [`Options`](crate::Options) are always looked up by the flattened configuration key,
+/// the strategy only affects rendering.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{Format, NamingStrategy, Options, Toml};
+/// use std::sync::Arc;
+///
+/// #[derive(Debug)]
+/// struct Upper;
+///
+/// impl NamingStrategy for Upper {
+///     fn flag_name(&self, key: &str) -> String {
+///         key.to_uppercase()
+///     }
+/// }
+///
+/// assert_eq!(
+///     Toml::from_slice_with(
+///         "port = 8080",
+///         &Options {
+///             naming: Some(Arc::new(Upper)),
+///             ..Default::default()
+///         },
+///     )
+///     .unwrap(),
+///     vec!["--PORT=8080"],
+/// );
+/// ```
+pub trait NamingStrategy: fmt::Debug + Send + Sync {
+    /// Returns the flag name for flattened configuration key `key`.
+    fn flag_name(&self, key: &str) -> String;
+}
+
+/// [`NamingStrategy`], which uses configuration keys as flag names unchanged.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Identity;
+
+impl NamingStrategy for Identity {
+    fn flag_name(&self, key: &str) -> String {
+        key.into()
+    }
+}
+
+/// [`NamingStrategy`], which converts configuration keys to kebab case, e.g. `max_retries` and
+/// `maxRetries` are both mapped to `max-retries`. Runs of capitals are treated as a single word,
+/// e.g. `HTTPServer` is mapped to `http-server`. Segments of nested keys are converted
+/// individually, e.g. `server.max_retries` is mapped to `server.max-retries`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KebabCase;
+
+impl NamingStrategy for KebabCase {
+    fn flag_name(&self, key: &str) -> String {
+        let mut name = String::with_capacity(key.len());
+        let mut prev = None;
+        let mut chars = key.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '_' | ' ' => name.push('-'),
+                c if c.is_uppercase() => {
+                    if prev.is_some_and(|p: char| {
+                        p.is_lowercase()
+                            || p.is_ascii_digit()
+                            || p.is_uppercase() && chars.peek().is_some_and(|n| n.is_lowercase())
+                    }) {
+                        name.push('-');
+                    }
+                    name.extend(c.to_lowercase());
+                }
+                c => name.push(c),
+            }
+            prev = Some(c);
+        }
+        name
+    }
+}
+
+/// [`NamingStrategy`], which prefixes configuration keys by a fixed string, e.g. with prefix
+/// `app-`, `port` is mapped to `app-port`.
+#[derive(Clone, Debug, Default)]
+pub struct Prefixed(pub String);
+
+impl NamingStrategy for Prefixed {
+    fn flag_name(&self, key: &str) -> String {
+        format!("{}{key}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kebab_case() {
+        for (key, name) in [
+            ("port", "port"),
+            ("max_retries", "max-retries"),
+            ("maxRetries", "max-retries"),
+            ("MaxRetries", "max-retries"),
+            ("server.max_retries", "server.max-retries"),
+            ("plugins.0.dataDir", "plugins.0.data-dir"),
+            ("HTTPServer", "http-server"),
+            ("serverURL", "server-url"),
+            ("getHTTPResponse", "get-http-response"),
+            ("sha256Sum", "sha256-sum"),
+        ] {
+            assert_eq!(KebabCase.flag_name(key), name, "key: {key}");
+        }
+    }
+
+    #[test]
+    fn prefixed() {
+        assert_eq!(Prefixed("app-".into()).flag_name("port"), "app-port");
+        assert_eq!(Identity.flag_name("server.port"), "server.port");
+    }
+}
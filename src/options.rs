@@ -0,0 +1,373 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{NamingStrategy, ValueKind};
+
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use toml::value::Table;
+
+/// Rendering of boolean elements of arrays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayBool {
+    /// `true` elements are rendered as `--key` and `false` elements are dropped, same as
+    /// boolean values outside of arrays.
+    #[default]
+    Flag,
+    /// Elements are rendered as `--key=true` and `--key=false`, which preserves the number and
+    /// order of elements.
+    Value,
+    /// `true` elements are counted and rendered as that many `--key` arguments, e.g. for flags
+    /// counting occurrences. `false` elements reset the count, i.e. only `true` elements
+    /// following the last `false` element are rendered.
+    Count,
+}
+
+/// Rendering of a table as repeated flag with `key=value` values, e.g. for flags parsed by a
+/// key-value parser.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{Format, Options, PairTable, Toml};
+/// use std::collections::HashMap;
+///
+/// assert_eq!(
+///     Toml::from_slice_with(
+///         "[labels]\nname = \"web\"\ntier = \"frontend\"",
+///         &Options {
+///             pair_tables: HashMap::from([("labels".into(), PairTable::new("label"))]),
+///             ..Default::default()
+///         },
+///     )
+///     .unwrap(),
+///     vec!["--label=name=web", "--label=tier=frontend"],
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PairTable {
+    /// Name of the flag, which is repeated for each key of the table.
+    pub flag: String,
+    /// Separator between keys and values of the table.
+    pub separator: char,
+}
+
+impl PairTable {
+    /// Returns [`PairTable`] rendering tables as flag `flag` with keys and values separated
+    /// by `=`.
+    pub fn new(flag: impl Into<String>) -> Self {
+        Self {
+            flag: flag.into(),
+            separator: '=',
+        }
+    }
+}
+
+/// Rendering of a table with boolean values as a single flag listing keys of all `true`
+/// values.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{BoolTable, Format, Options, Toml};
+/// use std::collections::HashMap;
+///
+/// assert_eq!(
+///     Toml::from_slice_with(
+///         "[features]\nfoo = true\nbar = false\nbaz = true",
+///         &Options {
+///             bool_tables: HashMap::from([("features".into(), BoolTable::new("features"))]),
+///             ..Default::default()
+///         },
+///     )
+///     .unwrap(),
+///     vec!["--features=baz,foo"],
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoolTable {
+    /// Name of the flag.
+    pub flag: String,
+    /// Delimiter between keys of `true` values.
+    pub delimiter: String,
+}
+
+impl BoolTable {
+    /// Returns [`BoolTable`] rendering tables as flag `flag` with keys delimited by `,`.
+    pub fn new(flag: impl Into<String>) -> Self {
+        Self {
+            flag: flag.into(),
+            delimiter: ",".into(),
+        }
+    }
+}
+
+/// Separator between flags and their values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Separator {
+    /// Values are rendered within the same argument separated by `=`, e.g. `--key=value`.
+    /// Values containing `=` are rendered unchanged, e.g. `--key=a=b`, since argument parsers
+    /// split arguments at the first `=` only.
+    #[default]
+    Equals,
+    /// Values are rendered as separate arguments, e.g. `--key` and `value`. Values are never
+    /// split, i.e. each value is a single argument even if it contains spaces.
+    Space,
+    /// Values are rendered as separate arguments, unless they start with `-`, e.g. `--key` and
+    /// `value`, but `--key=-5`. This prevents values from being interpreted as flags.
+    Smart,
+}
+
+/// Rendering of empty arrays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmptyArray {
+    /// Empty arrays are dropped, i.e. no arguments are produced.
+    #[default]
+    Drop,
+    /// Empty arrays are rendered as `--key=`, which allows for empty arrays to be distinguished
+    /// from missing keys.
+    Empty,
+    /// Empty arrays cause an error.
+    Error,
+}
+
+/// Rendering of flags of arrays with primitive elements.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayFlag {
+    /// The flag is repeated for each element in order of the elements, e.g.
+    /// `array = ["a", "b"]` is rendered as `--array=a` and `--array=b`, which matches repeated
+    /// flags collected into a `Vec` by clap.
+    #[default]
+    Repeated,
+    /// The flag is rendered once followed by all elements as separate arguments in order of the
+    /// elements, e.g. `array = ["a", "b"]` is rendered as `--array`, `a` and `b`, which matches
+    /// clap arguments accepting multiple values per occurrence, e.g. with `num_args(1..)` in
+    /// clap 4 or `multiple_values(true)` in clap 3. Boolean elements are rendered as `true` and
+    /// `false` and it is an error for such arrays to contain tables or arrays.
+    ///
+    /// Note, that elements starting with `-` are likely to be interpreted as flags by argument
+    /// parsers.
+    Once,
+}
+
+/// Rendering of tables contained in arrays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TableArray {
+    /// Keys of tables are prefixed by the array key and the element index, e.g.
+    /// `endpoint = [{ url = "a" }, { url = "b" }]` is rendered as `--endpoint.0.url=a` and
+    /// `--endpoint.1.url=b`.
+    #[default]
+    Indexed,
+    /// Keys of tables are prefixed by the array key joined by `separator`, such that each table
+    /// produces a group of repeated flags, e.g. with `-` separator
+    /// `endpoint = [{ url = "a" }, { url = "b" }]` is rendered as `--endpoint-url=a` and
+    /// `--endpoint-url=b`. Keys of tables nested within the elements are still joined by `.`.
+    /// Per-key options are looked up by keys joined by `.` without the index, e.g. `endpoint.url`.
+    ///
+    /// Note, that `false` values are dropped by default, therefore groups are only aligned
+    /// across repeated flags if all boolean keys are listed in [`Options::negatable_keys`].
+    Grouped {
+        /// Separator between the array key and the keys of its elements.
+        separator: char,
+    },
+}
+
+/// Options controlling how configuration files are read and how their values are rendered as
+/// arguments.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::{ArrayBool, Format, Options, Toml};
+///
+/// assert_eq!(
+///     Toml::from_slice_with(
+///         "flags = [true, false]",
+///         &Options {
+///             array_bool: ArrayBool::Value,
+///             ..Default::default()
+///         },
+///     )
+///     .unwrap(),
+///     vec!["--flags=true", "--flags=false"],
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Rendering of boolean elements of arrays.
+    pub array_bool: ArrayBool,
+    /// Rendering of flags of arrays with primitive elements.
+    pub array_flag: ArrayFlag,
+    /// Rendering of empty arrays.
+    pub empty_array: EmptyArray,
+    /// Rendering of tables contained in arrays.
+    pub table_array: TableArray,
+    /// Default values of top-level keys within [`Options::root`], which are rendered after all
+    /// other arguments, if the configuration does not contain the key.
+    pub defaults: Table,
+    /// Whether keys with values equal to their values in [`Options::defaults`] are omitted,
+    /// e.g. to only render changed values for display. If set, defaults of absent keys are not
+    /// rendered either.
+    pub omit_defaults: bool,
+    /// Dot-separated path of the table to generate arguments from, e.g. `tool.mytool`. If set,
+    /// all keys outside of the table are ignored and it is an error for the table to be missing.
+    pub root: Option<String>,
+    /// Name of a reserved flag, which selects [`Options::root`] at runtime, e.g. with
+    /// `config-section`, `--config-section=prod` selects table `prod`. The flag is removed from
+    /// the arguments and takes precedence over [`Options::root`], the last occurrence wins and an
+    /// empty value selects the top-level table. Only used by `args*` functions.
+    pub root_flag: Option<String>,
+    /// Name of the profile to generate arguments from, e.g. with `release`, only keys of table
+    /// `[profile.release]` are rendered and all other profiles are ignored, like Cargo profiles.
+    /// The profile table is looked up within [`Options::root`], if set, and it is an error for
+    /// it to be missing.
+    pub profile: Option<String>,
+    /// Name of a reserved flag, which selects [`Options::profile`] at runtime, e.g. with
+    /// `profile`, `--profile=release` selects profile `release`. Same as for
+    /// [`Options::root_flag`], the flag is removed from the arguments, the last occurrence wins
+    /// and an empty value selects no profile. Only used by `args*` functions.
+    pub profile_flag: Option<String>,
+    /// Set of known keys. If set, it is an error for the configuration to contain a key, which
+    /// is not in the set. Nested keys are specified in flattened form, e.g. `server.port`, and
+    /// listing a table key, e.g. `server`, allows all keys contained in it.
+    pub known_keys: Option<HashSet<String>>,
+    /// Set of keys, datetime values of which are rendered as the number of seconds since Unix
+    /// epoch. Datetimes without an offset are treated as UTC and it is an error for such values
+    /// to not contain a date.
+    pub unix_timestamp_keys: HashSet<String>,
+    /// Set of keys, datetime values of which are rendered without fractional seconds, e.g.
+    /// `01:02:03.000000004` is rendered as `01:02:03`.
+    pub whole_second_keys: HashSet<String>,
+    /// Whether datetime values with an offset are converted to UTC before rendering, e.g.
+    /// `2024-03-01T01:30:00+02:00` is rendered as `2024-02-29T23:30:00Z`. Datetimes without an
+    /// offset are rendered unchanged. It is an error for the year of a converted datetime to be
+    /// out of range, e.g. for `0000-01-01T00:00:00+01:00`.
+    pub utc_datetimes: bool,
+    /// Set of keys, non-finite float values of which are rendered as `inf`, `-inf` and `nan`.
+    /// Non-finite float values of all other keys are rejected.
+    pub non_finite_keys: HashSet<String>,
+    /// Expected kinds of values of keys. It is an error for the value of a key to be of a
+    /// different kind. Nested keys are specified in flattened form, e.g. `server.port`.
+    pub value_kinds: HashMap<String, ValueKind>,
+    /// Map of keys to allowed ranges of their integer values, e.g. with `port` mapped to
+    /// `1..=65535`, `port = 99999` causes an error naming the key and the range instead of being
+    /// rejected by the argument parser. This applies to integer values and integer elements of
+    /// arrays.
+    pub integer_ranges: HashMap<String, RangeInclusive<i64>>,
+    /// Set of keys, `false` values of which are rendered as `--key=false` instead of being
+    /// dropped.
+    pub negatable_keys: HashSet<String>,
+    /// Set of boolean keys, integer values of which are rendered as booleans, i.e. `1` is
+    /// rendered same as `true` and `0` is rendered same as `false`. It is an error for such keys
+    /// to have other integer values.
+    pub bool_keys: HashSet<String>,
+    /// Set of keys, string values of which are stripped of underscores if they represent a
+    /// number written using TOML digit separators, e.g. `"1_000_000"` is rendered as `1000000`.
+    /// Other strings, e.g. `"foo_bar"` or `"1__000"`, are rendered unchanged.
+    pub numeric_string_keys: HashSet<String>,
+    /// Map of array keys to separators, elements of which are joined by the separator into a
+    /// single argument instead of being rendered as repeated flags, e.g. with `tags` mapped to
+    /// `,`, `tags = ["a", "b"]` is rendered as `--tags=a,b`. It is an error for such arrays to
+    /// contain tables or arrays. Empty arrays are rendered according to
+    /// [`Options::empty_array`].
+    pub joined_keys: HashMap<String, String>,
+    /// Map of table keys to their [`PairTable`] rendering, e.g. with `labels` mapped to
+    /// `PairTable::new("label")`, `labels = { name = "web" }` is rendered as
+    /// `--label=name=web`. It is an error for values of such tables to not be primitive.
+    pub pair_tables: HashMap<String, PairTable>,
+    /// Map of table keys to their [`BoolTable`] rendering, e.g. with `features` mapped to
+    /// `BoolTable::new("features")`, `features = { foo = true, bar = false }` is rendered as
+    /// `--features=foo`. Keys are listed in order of their names and tables without `true`
+    /// values produce no arguments. It is an error for values of such tables to not be
+    /// booleans.
+    pub bool_tables: HashMap<String, BoolTable>,
+    /// Map of keys to suffixes appended to their integer and float values, e.g. with
+    /// `timeout` mapped to `s`, `timeout = 30` is rendered as `--timeout=30s`.
+    pub suffixes: HashMap<String, String>,
+    /// Map of keys to maps of aliases to values substituted for them, e.g. with `color` mapped
+    /// to a map of `yes` to `always`, `color = "yes"` is rendered as `--color=always`. This
+    /// applies to string values and string elements of arrays, values not in the map are
+    /// rendered unchanged.
+    pub value_aliases: HashMap<String, HashMap<String, String>>,
+    /// Whether keys are converted to lowercase before being validated and rendered, e.g.
+    /// `MaxRetries = 3` is rendered as `--maxretries=3`. This applies to keys nested within
+    /// tables, but not to [`Options::root`]. It is an error for two keys of the same table to
+    /// be equal after conversion, e.g. `Foo` and `foo`.
+    pub lowercase_keys: bool,
+    /// Whether tables, all keys of which are non-negative integers, e.g. `{"0": "a", "1": "b"}`,
+    /// are rendered as positional arguments ordered by the numeric value of the key, e.g. `a`
+    /// and `b`. Keys must be in canonical decimal form, i.e. tables with keys like `01` or `+1`
+    /// are rendered as flags. Only the top-level table is considered and it is an error for
+    /// values of such tables to not be primitive.
+    pub numeric_positionals: bool,
+    /// Maximum length in bytes of a single generated argument. If set, it is an error for an
+    /// argument, e.g. one containing a large string value, to exceed it, which is useful to
+    /// detect arguments, which would exceed the OS limits when passed to a subprocess.
+    pub max_arg_len: Option<usize>,
+    /// Separator between flags and their values. Boolean flags are not affected.
+    pub separator: Separator,
+    /// Predicate over flattened keys, which determines whether values of a key are rendered as
+    /// two separate arguments, e.g. `--key` and `value`, instead of `--key=value`, regardless of
+    /// [`Options::separator`]. Boolean flags are not affected.
+    pub separate_value: Option<fn(&str) -> bool>,
+    /// Whether configuration files, which have already been expanded, are skipped when
+    /// referenced again, e.g. `@conf.toml @conf.toml` is expanded once. Paths are compared in
+    /// canonicalized form.
+    pub dedup_paths: bool,
+    /// Whether a directive on the first line of a configuration file, e.g. `#!confargs toml`,
+    /// selects the format of the file instead of its extension when the format is chosen
+    /// automatically. The directive names a file extension of the format and it is stripped
+    /// before parsing.
+    pub format_directive: bool,
+    /// Whether `//` line comments and `/* */` block comments are allowed in JSON
+    /// configuration, which many JSON configuration files in practice contain. Comment
+    /// delimiters within strings are not affected. Requires `json` feature.
+    pub json_comments: bool,
+    /// Maximum number of arguments generated from a single configuration. If set, it is an
+    /// error for a configuration, e.g. one containing a large array, to produce more arguments.
+    /// All arguments are counted, including positionals and trailing arguments following `--`.
+    pub max_args: Option<usize>,
+    /// Reserved top-level key, elements of which are rendered verbatim after a `--` separator at
+    /// the end of the arguments, e.g. with `trailing`, `trailing = ["-v", "a"]` is rendered as
+    /// `--`, `-v` and `a`. It is an error for the value of such key to not be an array. It is
+    /// also an error for the key to be listed in [`Options::known_keys`] or
+    /// [`Options::defaults`], since it could not be used as a regular key. If not set, no key is
+    /// reserved and all keys are rendered as regular flags.
+    pub trailing_key: Option<String>,
+    /// Reserved top-level key of a table containing conditional sections, e.g. with `if`,
+    /// `[if.production]` is a section of `production` condition. Sections of conditions in
+    /// [`Options::active_conditions`] are merged into the configuration in order of their names,
+    /// overriding values of the same keys, all other sections are ignored. Same as with
+    /// [`Options::trailing_key`], it is an error for the key to be listed in
+    /// [`Options::known_keys`] or [`Options::defaults`].
+    pub condition_key: Option<String>,
+    /// Set of active conditions, see [`Options::condition_key`].
+    pub active_conditions: HashSet<String>,
+    /// Whether flags are rendered as environment variable assignments, e.g. `DB_HOST=x` instead
+    /// of `--db_host=x`, making the arguments suitable for setting the environment of a
+    /// process. Flag names are uppercased and all characters other than ASCII letters and
+    /// digits are replaced by `_`. Flags without a value are rendered as `KEY=true` and
+    /// [`Options::separator`] is ignored. It is an error to combine it with options producing
+    /// arguments other than flags, i.e. [`ArrayFlag::Once`], [`Options::numeric_positionals`]
+    /// and [`Options::trailing_key`].
+    pub env_vars: bool,
+    /// Strategy mapping flattened keys to flag names. If not set, keys are used as flag names
+    /// unchanged.
+    pub naming: Option<Arc<dyn NamingStrategy>>,
+    /// Prefix prepended to all flag names after applying [`Options::naming`], e.g. with
+    /// `app-`, `port = 8080` is rendered as `--app-port=8080`.
+    pub flag_prefix: Option<String>,
+    /// Separator joining segments of nested keys in flag names after applying
+    /// [`Options::naming`], e.g. with `-`, `server.port` is rendered as `--server-port`. If not
+    /// set, segments are joined by `.`. Per-key options are always looked up by keys joined by
+    /// `.`.
+    pub join_separator: Option<char>,
+    /// Whether configuration files, which do not exist, are skipped instead of causing an error.
+    /// Note, that other errors, e.g. parsing errors, are still returned.
+    pub skip_missing: bool,
+    /// Whether `{key}` references within string values are substituted by values of referenced
+    /// keys, e.g. `data = "{base}/data"`. Nested keys are referenced in flattened form, e.g.
+    /// `{server.host}`. `{{` and `}}` are used to escape literal `{` and `}`.
+    pub interpolate: bool,
+}
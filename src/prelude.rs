@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: Apache-2.0
+//
+//! Commonly used items.
+//!
+//! # Examples
+//!
+//! ```
+//! use confargs::prelude::*;
+//!
+//! let args = args::<Toml>(prefix_char_filter::<'@'>).expect("failed to parse configuration files");
+//! ```
+
+#[cfg(feature = "json")]
+pub use super::Json;
+pub use super::{
+    args, args_from, args_from_with, args_with, prefix_char_filter, Args, Filter, Format, Options,
+    Toml,
+};
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io::{self, Read};
+use std::time::Duration;
+
+/// Timeout of fetching a remote configuration.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum size in bytes of a remote configuration.
+const MAX_SIZE: u64 = 16 << 20;
+
+/// Returns `true` if `path` is a URL of a remote configuration.
+pub(crate) fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Fetches the remote configuration at `url`.
+pub(crate) fn fetch(url: &str) -> io::Result<Vec<u8>> {
+    fetch_with(url, TIMEOUT, MAX_SIZE)
+}
+
+fn fetch_with(url: &str, timeout: Duration, max_size: u64) -> io::Result<Vec<u8>> {
+    let res = ureq::AgentBuilder::new()
+        .timeout(timeout)
+        .build()
+        .get(url)
+        .call()
+        .map_err(|e| match e {
+            ureq::Error::Status(code, _) => io::Error::other(format!(
+                "failed to fetch `{url}`: unexpected status code {code}"
+            )),
+            ureq::Error::Transport(e) => io::Error::other(format!("failed to fetch `{url}`: {e}")),
+        })?;
+    let mut buf = vec![];
+    let _ = res.into_reader().take(max_size + 1).read_to_end(&mut buf)?;
+    if buf.len() as u64 > max_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("failed to fetch `{url}`: response exceeds maximum size of {max_size} bytes"),
+        ));
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serves a single HTTP response with `status` and `body` and returns the URL.
+    fn serve(status: &'static str, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind listener");
+        let addr = listener.local_addr().expect("failed to get address");
+        let _ = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("failed to accept connection");
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).expect("failed to read request");
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .expect("failed to write response");
+        });
+        format!("http://{addr}/config.toml")
+    }
+
+    #[test]
+    fn fetch() {
+        assert!(is_url("https://example.com/config.toml"));
+        assert!(!is_url("config.toml"));
+
+        assert_eq!(
+            super::fetch(&serve("200 OK", r#"string = "foo""#)).unwrap(),
+            br#"string = "foo""#
+        );
+        assert!(super::fetch(&serve("404 Not Found", ""))
+            .unwrap_err()
+            .to_string()
+            .ends_with("unexpected status code 404"));
+        assert!(fetch_with(&serve("200 OK", "0123456789"), TIMEOUT, 9)
+            .unwrap_err()
+            .to_string()
+            .ends_with("response exceeds maximum size of 9 bytes"));
+        assert_eq!(
+            fetch_with(&serve("200 OK", "0123456789"), TIMEOUT, 10).unwrap(),
+            b"0123456789"
+        );
+    }
+}
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Utilities for testing [`Format`] implementations, requires `testing` feature.
+
+use super::Format;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Asserts that both [`Format::from_slice`] and [`Format::read`] parse `buf` into `expected`
+/// arguments.
+///
+/// [`Format::read`] is called on a temporary file containing `buf`, which is removed afterwards.
+///
+/// # Panics
+///
+/// Panics if parsing fails, if the arguments produced do not match `expected` or if the
+/// temporary file cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// use confargs::Toml;
+///
+/// confargs::testing::assert_format::<Toml>(
+///     r#"string = "foo"
+/// integer = 42"#,
+///     &["--integer=42", "--string=foo"],
+/// );
+/// ```
+pub fn assert_format<T: Format>(buf: impl AsRef<[u8]>, expected: &[&str]) {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let buf = buf.as_ref();
+    assert_eq!(
+        T::from_slice(buf)
+            .expect("failed to parse slice")
+            .into_iter()
+            .collect::<Vec<_>>(),
+        expected,
+        "arguments parsed from slice do not match"
+    );
+
+    let path = env::temp_dir().join(format!(
+        "confargs-testing-{}-{}",
+        process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&path, buf).expect("failed to write temporary file");
+    let args = T::read(&path);
+    let _ = fs::remove_file(&path);
+    assert_eq!(
+        args.expect("failed to read file")
+            .into_iter()
+            .collect::<Vec<_>>(),
+        expected,
+        "arguments read from file do not match"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Toml;
+
+    #[test]
+    fn toml() {
+        assert_format::<Toml>(
+            r#"string = "foo"
+array = [1, 2]
+
+[server]
+port = 8080"#,
+            &[
+                "--array=1",
+                "--array=2",
+                "--server.port=8080",
+                "--string=foo",
+            ],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "arguments parsed from slice do not match")]
+    fn mismatch() {
+        assert_format::<Toml>("integer = 42", &["--integer=43"]);
+    }
+}
@@ -1,79 +1,949 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{parse_bool_arg, parse_string_arg, Format};
+use super::{
+    strip_bom, ArrayBool, ArrayFlag, BoolTable, EmptyArray, Error, Format, Options, PairTable,
+    Position, Separator, TableArray, ValueKind,
+};
 
-use std::collections::VecDeque;
-use std::fmt::Display;
-use std::{io, vec};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt::{self, Display};
+use std::io;
+use std::iter::once;
 
 use anyhow::{bail, Result};
-use toml::Value;
+use serde::de::{Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use toml::value::{Date, Datetime, Offset, Table};
+use toml::{Spanned, Value};
 
-fn parse_primitive_arg(k: impl Display, v: Value) -> Result<Option<String>> {
+/// Returns the number of seconds since Unix epoch of `dt`, if it contains a date.
+///
+/// Datetimes without an offset are treated as UTC.
+fn unix_timestamp(dt: &Datetime) -> Option<i64> {
+    let Date { year, month, day } = dt.date.clone()?;
+
+    // Days since epoch computation based on http://howardhinnant.github.io/date_algorithms.html
+    let (month, day) = (i64::from(month), i64::from(day));
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = dt.time.as_ref().map_or(0, |t| {
+        i64::from(t.hour) * 3600 + i64::from(t.minute) * 60 + i64::from(t.second)
+    });
+    let offset = match dt.offset {
+        None | Some(Offset::Z) => 0,
+        Some(Offset::Custom { hours, minutes }) if hours < 0 => {
+            i64::from(hours) * 60 - i64::from(minutes)
+        }
+        Some(Offset::Custom { hours, minutes }) => i64::from(hours) * 60 + i64::from(minutes),
+    };
+    Some(days * 86_400 + secs - offset * 60)
+}
+
+/// Returns `dt` converted to UTC, if it contains a date, a time and an offset.
+///
+/// Returns `None` if the year of the converted datetime is out of range of TOML datetimes, e.g.
+/// for `0000-01-01T00:00:00+01:00`.
+fn utc_datetime(mut dt: Datetime) -> Option<Datetime> {
+    if dt.offset.is_none() {
+        return Some(dt);
+    }
+    let (Some(ts), Some(time)) = (unix_timestamp(&dt), dt.time.as_mut()) else {
+        return Some(dt);
+    };
+    let (days, secs) = (ts.div_euclid(86_400), ts.rem_euclid(86_400));
+    // Date from days since epoch computation based on
+    // http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = u16::try_from(yoe + era * 400 + i64::from(month <= 2))
+        .ok()
+        .filter(|year| *year <= 9999)?;
+    // All other values are in range for an offset datetime with a valid date.
+    dt.date = Some(Date {
+        year,
+        month: month as u8,
+        day: day as u8,
+    });
+    time.hour = (secs / 3600) as u8;
+    time.minute = (secs % 3600 / 60) as u8;
+    time.second = (secs % 60) as u8;
+    dt.offset = Some(Offset::Z);
+    Some(dt)
+}
+
+/// Returns `v` with underscores removed, if `v` is a decimal number with digits optionally
+/// separated by single underscores, e.g. `-1_000.000_1`.
+fn strip_digit_separators(v: &str) -> Option<String> {
+    let digits = |s: &str| {
+        s.split('_')
+            .all(|g| !g.is_empty() && g.bytes().all(|b| b.is_ascii_digit()))
+    };
+    let n = v.strip_prefix(['+', '-']).unwrap_or(v);
+    let valid = match n.split_once('.') {
+        Some((int, frac)) => digits(int) && digits(frac),
+        None => digits(n),
+    };
+    valid.then(|| v.replace('_', ""))
+}
+
+/// Argument rendered from a configuration value, before flag names are derived from keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Arg {
+    /// Flag of flattened key `key`, the name of which is derived from the key using [`Options`].
+    /// `name` overrides the key in the name, e.g. for elements of [`TableArray::Grouped`] arrays.
+    Flag {
+        key: String,
+        name: Option<String>,
+        value: Option<String>,
+    },
+    /// Flag with a name fixed by [`Options`], e.g. [`PairTable::flag`], which is not renamed.
+    Named { name: String, value: Option<String> },
+    /// Argument rendered verbatim, e.g. an element of an [`ArrayFlag::Once`] array.
+    Raw(String),
+}
+
+impl Arg {
+    /// Returns the length of the argument rendered without renaming.
+    fn len(&self) -> usize {
+        self.to_string().len()
+    }
+}
+
+impl Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, value) = match self {
+            Self::Flag { key, name, value } => (name.as_ref().unwrap_or(key), value),
+            Self::Named { name, value } => (name, value),
+            Self::Raw(arg) => return f.write_str(arg),
+        };
+        match value {
+            Some(v) => write!(f, "--{name}={v}"),
+            None => write!(f, "--{name}"),
+        }
+    }
+}
+
+fn parse_string_arg(k: impl Display, v: impl Display) -> Arg {
+    Arg::Flag {
+        key: k.to_string(),
+        name: None,
+        value: Some(v.to_string()),
+    }
+}
+
+fn parse_bool_arg(k: impl Display, v: bool) -> Option<Arg> {
+    v.then(|| Arg::Flag {
+        key: k.to_string(),
+        name: None,
+        value: None,
+    })
+}
+
+/// Returns the flag name of flattened key `key` derived using [`Options::naming`],
+/// [`Options::join_separator`] and [`Options::flag_prefix`].
+fn flag_name(key: &str, opts: &Options) -> String {
+    let mut name = match opts.naming.as_ref() {
+        Some(naming) => naming.flag_name(key),
+        None => key.into(),
+    };
+    if let Some(sep) = opts.join_separator.filter(|sep| *sep != '.') {
+        name = name.replace('.', sep.encode_utf8(&mut [0; 4]));
+    }
+    if let Some(prefix) = opts.flag_prefix.as_deref() {
+        name.insert_str(0, prefix);
+    }
+    name
+}
+
+fn parse_primitive_arg(k: impl Display, v: Value, opts: &Options) -> Result<Option<Arg>> {
+    let k = k.to_string();
     match v {
-        Value::String(v) => Ok(parse_string_arg(k, v).into()),
-        Value::Integer(v) => Ok(parse_string_arg(k, v).into()),
-        Value::Float(v) => Ok(parse_string_arg(k, v).into()),
-        Value::Boolean(v) => Ok(parse_bool_arg(k, v)),
-        Value::Datetime(v) => Ok(parse_string_arg(k, v).into()),
-        Value::Array(_) => bail!("array not supported for field `{k}`"),
-        Value::Table(_) => bail!("table not supported for field `{k}`"),
+        Value::String(v) => match opts
+            .value_aliases
+            .get(&k)
+            .and_then(|aliases| aliases.get(&v))
+        {
+            Some(v) => Ok(parse_string_arg(&k, v).into()),
+            None if opts.numeric_string_keys.contains(&k) => match strip_digit_separators(&v) {
+                Some(n) => Ok(parse_string_arg(&k, n).into()),
+                None => Ok(parse_string_arg(&k, v).into()),
+            },
+            None => Ok(parse_string_arg(&k, v).into()),
+        },
+        Value::Integer(v)
+            if opts
+                .integer_ranges
+                .get(&k)
+                .is_some_and(|range| !range.contains(&v)) =>
+        {
+            let range = &opts.integer_ranges[&k];
+            bail!(Error::new(
+                k.clone(),
+                format!(
+                    "integer `{v}` of field `{k}` is out of range `{}..={}`",
+                    range.start(),
+                    range.end()
+                )
+            )
+            .with_kind(ValueKind::Integer))
+        }
+        Value::Integer(v) if opts.bool_keys.contains(&k) => match v {
+            0 | 1 => parse_primitive_arg(&k, Value::Boolean(v == 1), opts),
+            _ => bail!(Error::new(
+                k.clone(),
+                format!("integer `{v}` is not a valid boolean for field `{k}`")
+            )
+            .with_kind(ValueKind::Integer)),
+        },
+        Value::Integer(v) => match opts.suffixes.get(&k) {
+            Some(suffix) => Ok(parse_string_arg(&k, format!("{v}{suffix}")).into()),
+            None => Ok(parse_string_arg(&k, v).into()),
+        },
+        Value::Float(v) if !v.is_finite() => {
+            if !opts.non_finite_keys.contains(&k) {
+                bail!(Error::new(
+                    k.clone(),
+                    format!("non-finite float `{v}` not supported for field `{k}`")
+                )
+                .with_kind(ValueKind::Float))
+            }
+            Ok(parse_string_arg(
+                k,
+                if v.is_nan() {
+                    "nan"
+                } else if v.is_sign_positive() {
+                    "inf"
+                } else {
+                    "-inf"
+                },
+            )
+            .into())
+        }
+        Value::Float(v) => match opts.suffixes.get(&k) {
+            Some(suffix) => Ok(parse_string_arg(&k, format!("{v}{suffix}")).into()),
+            None => Ok(parse_string_arg(&k, v).into()),
+        },
+        Value::Boolean(false) if opts.negatable_keys.contains(&k) => {
+            Ok(parse_string_arg(&k, false).into())
+        }
+        Value::Boolean(v) => Ok(parse_bool_arg(&k, v)),
+        Value::Datetime(v)
+            if opts.utc_datetimes && matches!(v.offset, Some(Offset::Custom { .. })) =>
+        {
+            match utc_datetime(v) {
+                Some(v) => parse_primitive_arg(&k, Value::Datetime(v), opts),
+                None => bail!(Error::new(
+                    k.clone(),
+                    format!("UTC datetime out of range for field `{k}`")
+                )
+                .with_kind(ValueKind::Datetime)),
+            }
+        }
+        Value::Datetime(v) if opts.unix_timestamp_keys.contains(&k) => match unix_timestamp(&v) {
+            Some(ts) => Ok(parse_string_arg(&k, ts).into()),
+            None => bail!(Error::new(
+                k.clone(),
+                format!("Unix timestamp of datetime without date not supported for field `{k}`")
+            )
+            .with_kind(ValueKind::Datetime)),
+        },
+        Value::Datetime(mut v) if opts.whole_second_keys.contains(&k) => {
+            if let Some(time) = v.time.as_mut() {
+                time.nanosecond = 0;
+            }
+            Ok(parse_string_arg(&k, v).into())
+        }
+        Value::Datetime(v) => Ok(parse_string_arg(&k, v).into()),
+        Value::Array(_) => bail!(Error::new(
+            k.clone(),
+            format!("array not supported for field `{k}`")
+        )
+        .with_kind(ValueKind::Array)),
+        Value::Table(_) => bail!(Error::new(
+            k.clone(),
+            format!("table not supported for field `{k}`")
+        )
+        .with_kind(ValueKind::Table)),
     }
 }
 
-struct ArrayIterator<K> {
-    key: K,
+/// Iterator over arguments produced by an array value.
+///
+/// Primitive elements are rendered using the array key, while table elements are flattened
+/// according to [`TableArray`], by default using the element index as an additional key
+/// segment, e.g. `key.0.subkey`. Indices are positions within the array and therefore start at
+/// `0` for every array.
+struct ArrayIterator<'a> {
+    key: String,
     values: VecDeque<Value>,
+    index: usize,
+    /// Number of leading elements, within which boolean elements are dropped.
+    reset: usize,
+    table: Option<ArgIterator<'a>>,
+    opts: &'a Options,
 }
 
-impl<K> ArrayIterator<K> {
-    fn new(key: K, values: Vec<Value>) -> Self {
+impl<'a> ArrayIterator<'a> {
+    fn new(key: String, values: Vec<Value>, opts: &'a Options) -> Self {
+        let reset = match opts.array_bool {
+            ArrayBool::Count => values
+                .iter()
+                .rposition(|v| matches!(v, Value::Boolean(false)))
+                .map_or(0, |i| i + 1),
+            ArrayBool::Flag | ArrayBool::Value => 0,
+        };
         Self {
             key,
             values: values.into(),
+            index: 0,
+            reset,
+            table: None,
+            opts,
         }
     }
 }
 
-impl<K: Display + Copy> Iterator for ArrayIterator<K> {
-    type Item = Result<String>;
+impl Iterator for ArrayIterator<'_> {
+    type Item = Result<Arg>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if let Some(arg) = self
-                .values
-                .pop_front()
-                .map(|v| parse_primitive_arg(self.key, v))?
-                .transpose()
-            {
+            if let Some(arg) = self.table.as_mut().and_then(Iterator::next) {
                 return Some(arg);
             }
+            self.table = None;
+
+            let v = self.values.pop_front()?;
+            let i = self.index;
+            self.index += 1;
+            match v {
+                Value::Table(kv) => {
+                    self.table =
+                        Some(match self.opts.table_array {
+                            TableArray::Indexed => {
+                                parse_table(format!("{}.{i}", self.key), kv, self.opts)
+                            }
+                            TableArray::Grouped { separator } => {
+                                let n = self.key.len();
+                                Box::new(parse_table(self.key.clone(), kv, self.opts).map(
+                                    move |arg| match arg? {
+                                        Arg::Flag { key, name, value } => {
+                                            let mut name = name.unwrap_or_else(|| key.clone());
+                                            name.replace_range(
+                                                n..n + 1,
+                                                separator.encode_utf8(&mut [0; 4]),
+                                            );
+                                            Ok(Arg::Flag {
+                                                key,
+                                                name: Some(name),
+                                                value,
+                                            })
+                                        }
+                                        arg => Ok(arg),
+                                    },
+                                ))
+                            }
+                        })
+                }
+                Value::Boolean(v) if self.opts.array_bool == ArrayBool::Value => {
+                    return Some(Ok(parse_string_arg(&self.key, v)))
+                }
+                Value::Boolean(_) if i < self.reset => {}
+                Value::Array(_) => {
+                    return Some(Err(Error::new(
+                        format!("{}.{i}", self.key),
+                        format!(
+                            "nested array at index {i} of field `{}` not supported",
+                            self.key
+                        ),
+                    )
+                    .with_kind(ValueKind::Array)
+                    .into()))
+                }
+                v => {
+                    if let Some(arg) = parse_primitive_arg(&self.key, v, self.opts).transpose() {
+                        return Some(arg);
+                    }
+                }
+            }
+        }
+    }
+}
+
+type ArgIterator<'a> = Box<dyn Iterator<Item = Result<Arg>> + 'a>;
+
+/// Flattens table `kv` using `k` joined with each key of `kv` by `sep` as keys.
+fn parse_table(k: String, kv: Table, opts: &Options) -> ArgIterator<'_> {
+    Box::new(kv.into_iter().flat_map(move |(sk, v)| {
+        match parse_arg(format!("{k}.{sk}"), v, opts) {
+            Ok(args) => args,
+            Err(e) => Box::new(once(Err(e))),
+        }
+    }))
+}
+
+/// Ensures value `v` at key `k` is of the kind specified in [`Options::value_kinds`], if any.
+fn check_kind(k: impl Display, v: &Value, opts: &Options) -> Result<()> {
+    if opts.value_kinds.is_empty() {
+        return Ok(());
+    }
+    let k = k.to_string();
+    let kind = kind_of(v);
+    match opts.value_kinds.get(&k) {
+        Some(&expected) if expected != kind => {
+            bail!(Error::new(
+                k.clone(),
+                format!("expected {expected} for field `{k}`, found {kind}")
+            )
+            .with_kind(kind))
         }
+        _ => Ok(()),
     }
 }
 
-fn parse_arg<'a>(
-    k: impl Display + Copy + 'a,
-    v: Value,
-) -> Result<Box<dyn Iterator<Item = Result<String>> + 'a>> {
+fn parse_arg(k: impl Display, v: Value, opts: &Options) -> Result<ArgIterator<'_>> {
+    check_kind(&k, &v, opts)?;
     match v {
         Value::String(_)
         | Value::Integer(_)
         | Value::Float(_)
         | Value::Boolean(_)
         | Value::Datetime(_) => {
-            let arg = parse_primitive_arg(k, v)?;
+            let arg = parse_primitive_arg(k, v, opts)?;
             Ok(Box::new(arg.map(Ok).into_iter()))
         }
-        Value::Array(vs) => Ok(Box::new(ArrayIterator::new(k, vs))),
-        Value::Table(_) => bail!("table not supported for field `{k}`"),
+        Value::Array(vs) if vs.is_empty() => match opts.empty_array {
+            EmptyArray::Drop => Ok(Box::new(None.into_iter())),
+            EmptyArray::Empty => Ok(Box::new(once(Ok(parse_string_arg(k, ""))))),
+            EmptyArray::Error => bail!(Error::new(
+                k.to_string(),
+                format!("empty array not supported for field `{k}`")
+            )
+            .with_kind(ValueKind::Array)),
+        },
+        Value::Array(vs) => match opts.joined_keys.get(&k.to_string()) {
+            Some(sep) => {
+                let vs = vs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| parse_value(&format!("{k}.{i}"), v))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Box::new(once(Ok(parse_string_arg(k, vs.join(sep))))))
+            }
+            None if opts.array_flag == ArrayFlag::Once => {
+                let vs = vs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| parse_value(&format!("{k}.{i}"), v).map(|v| Ok(Arg::Raw(v))))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Box::new(
+                    once(Ok(Arg::Flag {
+                        key: k.to_string(),
+                        name: None,
+                        value: None,
+                    }))
+                    .chain(vs),
+                ))
+            }
+            None => Ok(Box::new(ArrayIterator::new(k.to_string(), vs, opts))),
+        },
+        Value::Table(kv) if opts.bool_tables.contains_key(&k.to_string()) => {
+            let BoolTable { flag, delimiter } = &opts.bool_tables[&k.to_string()];
+            let mut enabled = vec![];
+            for (sk, v) in kv {
+                match v {
+                    Value::Boolean(true) => enabled.push(sk),
+                    Value::Boolean(false) => {}
+                    v => bail!(Error::new(
+                        format!("{k}.{sk}"),
+                        format!("non-boolean value of field `{k}.{sk}` not supported")
+                    )
+                    .with_kind(kind_of(&v))),
+                }
+            }
+            Ok(Box::new(
+                (!enabled.is_empty())
+                    .then(|| {
+                        Ok(Arg::Named {
+                            name: flag.clone(),
+                            value: Some(enabled.join(delimiter)),
+                        })
+                    })
+                    .into_iter(),
+            ))
+        }
+        Value::Table(kv) => match opts.pair_tables.get(&k.to_string()) {
+            Some(PairTable { flag, separator }) => {
+                let vs = kv
+                    .iter()
+                    .map(|(sk, v)| {
+                        parse_value(&format!("{k}.{sk}"), v).map(|v| Arg::Named {
+                            name: flag.clone(),
+                            value: Some(format!("{sk}{separator}{v}")),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(Box::new(vs.into_iter()))
+            }
+            None => Ok(parse_table(k.to_string(), kv, opts)),
+        },
+    }
+}
+
+/// Collects all primitive values contained in `v` at flattened key `k`.
+fn collect_primitives(k: String, v: &Value, values: &mut HashMap<String, Value>) {
+    match v {
+        Value::Table(kv) => kv
+            .iter()
+            .for_each(|(sk, v)| collect_primitives(format!("{k}.{sk}"), v, values)),
+        Value::Array(vs) => vs
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| collect_primitives(format!("{k}.{i}"), v, values)),
+        v => {
+            let _ = values.insert(k, v.clone());
+        }
+    }
+}
+
+/// Resolves `{key}` references within string values.
+struct Interpolator {
+    values: HashMap<String, Value>,
+    resolved: HashMap<String, String>,
+    stack: Vec<String>,
+}
+
+impl Interpolator {
+    fn new(kv: &Table) -> Self {
+        let mut values = HashMap::new();
+        kv.iter()
+            .for_each(|(k, v)| collect_primitives(k.clone(), v, &mut values));
+        Self {
+            values,
+            resolved: HashMap::new(),
+            stack: vec![],
+        }
+    }
+
+    /// Returns the value at flattened key `k` with all references resolved.
+    fn resolve(&mut self, k: &str) -> Result<String> {
+        if let Some(v) = self.resolved.get(k) {
+            return Ok(v.clone());
+        }
+        let v = match self.values.get(k) {
+            Some(Value::String(v)) => v.clone(),
+            Some(Value::Integer(v)) => return Ok(v.to_string()),
+            Some(Value::Float(v)) => return Ok(v.to_string()),
+            Some(Value::Boolean(v)) => return Ok(v.to_string()),
+            Some(Value::Datetime(v)) => return Ok(v.to_string()),
+            Some(Value::Array(_) | Value::Table(_)) | None => {
+                bail!(Error::new(k, format!("field `{k}` cannot be referenced")))
+            }
+        };
+        if self.stack.iter().any(|sk| sk == k) {
+            bail!(Error::new(k, format!("cyclic reference to field `{k}`")))
+        }
+        self.stack.push(k.into());
+        let v = self.substitute(k, &v);
+        let _ = self.stack.pop();
+        let v = v?;
+        let _ = self.resolved.insert(k.into(), v.clone());
+        Ok(v)
+    }
+
+    /// Substitutes all references in `v` of field `k`, `{{` and `}}` are unescaped to `{` and
+    /// `}` respectively.
+    fn substitute(&mut self, k: &str, mut v: &str) -> Result<String> {
+        let mut out = String::with_capacity(v.len());
+        while let Some(i) = v.find(['{', '}']) {
+            out.push_str(&v[..i]);
+            let c = &v[i..=i];
+            v = &v[i + 1..];
+            if let Some(tail) = v.strip_prefix(c) {
+                out.push_str(c);
+                v = tail;
+            } else if c == "}" {
+                out.push('}');
+            } else if let Some((name, tail)) = v.split_once('}') {
+                if !self.values.contains_key(name) {
+                    bail!(Error::new(
+                        k,
+                        format!("unknown field `{name}` referenced in field `{k}`")
+                    ))
+                }
+                out.push_str(&self.resolve(name)?);
+                v = tail;
+            } else {
+                bail!(Error::new(
+                    k,
+                    format!("unterminated reference in field `{k}`")
+                ))
+            }
+        }
+        out.push_str(v);
+        Ok(out)
+    }
+
+    /// Resolves references in all string values contained in `v` at flattened key `k`.
+    fn interpolate(&mut self, k: String, v: &mut Value) -> Result<()> {
+        match v {
+            Value::String(v) => {
+                *v = self.resolve(&k)?;
+                Ok(())
+            }
+            Value::Table(kv) => self.interpolate_table(Some(&k), kv),
+            Value::Array(vs) => vs
+                .iter_mut()
+                .enumerate()
+                .try_for_each(|(i, v)| self.interpolate(format!("{k}.{i}"), v)),
+            _ => Ok(()),
+        }
+    }
+
+    fn interpolate_table(&mut self, prefix: Option<&str>, kv: &mut Table) -> Result<()> {
+        kv.iter_mut().try_for_each(|(k, v)| {
+            let k = prefix.map_or_else(|| k.clone(), |prefix| format!("{prefix}.{k}"));
+            self.interpolate(k, v)
+        })
+    }
+}
+
+fn select_root(kv: Table, root: &str) -> Result<Table> {
+    root.split('.').enumerate().try_fold(kv, |mut kv, (i, k)| {
+        let path = root.split('.').take(i + 1).collect::<Vec<_>>().join(".");
+        match kv.remove(k) {
+            Some(Value::Table(kv)) => Ok(kv),
+            Some(_) => bail!("`{path}` is not a table"),
+            None => bail!("table `{path}` not found"),
+        }
+    })
+}
+
+fn lowercase_value(k: &str, v: Value) -> Result<Value> {
+    match v {
+        Value::Table(kv) => lowercase_keys(Some(k), kv).map(Value::Table),
+        Value::Array(vs) => vs
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| lowercase_value(&format!("{k}.{i}"), v))
+            .collect::<Result<_>>()
+            .map(Value::Array),
+        v => Ok(v),
+    }
+}
+
+fn lowercase_keys(prefix: Option<&str>, kv: Table) -> Result<Table> {
+    kv.into_iter().try_fold(Table::new(), |mut kv, (k, v)| {
+        let lower = k.to_lowercase();
+        let path = prefix.map_or_else(|| lower.clone(), |prefix| format!("{prefix}.{lower}"));
+        let v = lowercase_value(&path, v)?;
+        if kv.insert(lower, v).is_some() {
+            bail!(Error::new(
+                path.clone(),
+                format!("duplicate key `{path}` after converting `{k}` to lowercase")
+            ))
+        }
+        Ok(kv)
+    })
+}
+
+/// Returns values of `kv` ordered by their keys, if all keys of `kv` are integers in canonical
+/// decimal form, i.e. without signs and leading zeros.
+fn numeric_positionals(kv: &Table) -> Option<Vec<(u64, &str, &Value)>> {
+    if kv.is_empty() {
+        return None;
+    }
+    let mut vs = kv
+        .iter()
+        .map(|(k, v)| {
+            k.parse()
+                .ok()
+                .filter(|i: &u64| i.to_string() == *k)
+                .map(|i| (i, k.as_str(), v))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    vs.sort_by_key(|(i, ..)| *i);
+    Some(vs)
+}
+
+/// Renders primitive value `v` at key `k` without the key, e.g. as a positional argument.
+fn parse_value(k: &str, v: &Value) -> Result<String> {
+    match v {
+        Value::String(v) => Ok(v.clone()),
+        Value::Integer(v) => Ok(v.to_string()),
+        Value::Float(v) => Ok(v.to_string()),
+        Value::Boolean(v) => Ok(v.to_string()),
+        Value::Datetime(v) => Ok(v.to_string()),
+        Value::Array(_) => bail!(
+            Error::new(k, format!("array not supported for field `{k}`"))
+                .with_kind(ValueKind::Array)
+        ),
+        Value::Table(_) => bail!(
+            Error::new(k, format!("table not supported for field `{k}`"))
+                .with_kind(ValueKind::Table)
+        ),
+    }
+}
+
+/// Merges table `src` into `dst`, merging nested tables and replacing all other values.
+fn merge_table(dst: &mut Table, src: Table) {
+    for (k, v) in src {
+        match (dst.get_mut(&k), v) {
+            (Some(Value::Table(dst)), Value::Table(src)) => merge_table(dst, src),
+            (_, v) => {
+                let _ = dst.insert(k, v);
+            }
+        }
+    }
+}
+
+/// Merges sections of conditional table `v` at key `k` named by active conditions into `kv`.
+fn apply_conditions(k: &str, v: Value, kv: &mut Table, opts: &Options) -> Result<()> {
+    let Value::Table(sections) = v else {
+        bail!(Error::new(k, format!("field `{k}` is not a table")).with_kind(kind_of(&v)))
+    };
+    for (name, section) in sections {
+        if !opts.active_conditions.contains(&name) {
+            continue;
+        }
+        match section {
+            Value::Table(section) => merge_table(kv, section),
+            v => bail!(Error::new(
+                format!("{k}.{name}"),
+                format!("field `{k}.{name}` is not a table")
+            )
+            .with_kind(kind_of(&v))),
+        }
+    }
+    Ok(())
+}
+
+/// Returns an error if `n` arguments, the last of which rendered for field `k`, exceed
+/// [`Options::max_args`].
+fn check_max_args(n: usize, k: &str, opts: &Options) -> Result<()> {
+    match opts.max_args {
+        Some(max) if n > max => bail!(Error::new(
+            k,
+            format!("number of arguments exceeds maximum of {max} at field `{k}`")
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Renders array `v` at key `k` as `--` followed by its elements.
+fn parse_trailing(k: &str, v: Value) -> Result<Vec<String>> {
+    match v {
+        Value::Array(vs) => once(Ok("--".into()))
+            .chain(
+                vs.iter()
+                    .enumerate()
+                    .map(|(i, v)| parse_value(&format!("{k}.{i}"), v)),
+            )
+            .collect(),
+        v => bail!(Error::new(k, format!("field `{k}` is not an array")).with_kind(kind_of(&v))),
+    }
+}
+
+fn kind_of(v: &Value) -> ValueKind {
+    match v {
+        Value::String(_) => ValueKind::String,
+        Value::Integer(_) => ValueKind::Integer,
+        Value::Float(_) => ValueKind::Float,
+        Value::Boolean(_) => ValueKind::Boolean,
+        Value::Datetime(_) => ValueKind::Datetime,
+        Value::Array(_) => ValueKind::Array,
+        Value::Table(_) => ValueKind::Table,
+    }
+}
+
+fn check_key(k: String, v: &Value, known: &HashSet<String>) -> Result<()> {
+    if known.contains(&k) {
+        return Ok(());
+    }
+    match v {
+        Value::Table(kv) => check_keys(Some(&k), kv, known),
+        Value::Array(vs) if vs.iter().any(Value::is_table) => {
+            vs.iter().enumerate().try_for_each(|(i, v)| match v {
+                Value::Table(_) => check_key(format!("{k}.{i}"), v, known),
+                _ => bail!(Error::new(format!("{k}.{i}"), format!("unknown key `{k}`"))),
+            })
+        }
+        _ => bail!(Error::new(k.clone(), format!("unknown key `{k}`"))),
+    }
+}
+
+fn check_keys(prefix: Option<&str>, kv: &Table, known: &HashSet<String>) -> Result<()> {
+    kv.iter().try_for_each(|(k, v)| {
+        let k = prefix.map_or_else(|| k.clone(), |prefix| format!("{prefix}.{k}"));
+        check_key(k, v, known)
+    })
+}
+
+/// Describes TOML parsing error `e` of `buf`, detecting non-TOML data following a valid
+/// document.
+///
+/// Data is only considered trailing if the line `e` occurred at does not resemble TOML, i.e. it is
+/// neither a table header, a comment nor a key/value pair. Errors within such constructs, e.g.
+/// unterminated strings, duplicate keys or table redefinitions, are returned unchanged.
+fn describe_parse_error(buf: &[u8], e: toml::de::Error) -> String {
+    let Some((line, column)) = e.line_col() else {
+        return e.to_string();
+    };
+    let start = buf
+        .split_inclusive(|&b| b == b'\n')
+        .take(line)
+        .map(<[u8]>::len)
+        .sum();
+    let (head, tail) = buf.split_at(start);
+    if line == 0 || toml::from_slice::<Table>(head).is_err() {
+        return e.to_string();
+    }
+    let first = tail.split(|&b| b == b'\n').next().unwrap_or_default();
+    let first = first.trim_ascii_start();
+    if first.starts_with(b"[")
+        || first.starts_with(b"#")
+        || first.contains(&b'=')
+        || toml::from_slice::<Table>(tail).is_ok()
+    {
+        return e.to_string();
+    }
+    format!(
+        "trailing data after a valid document at line {}, column {}: {e}",
+        line + 1,
+        column + 1
+    )
+}
+
+/// Source spans of all values within a TOML document.
+#[derive(Debug, Default)]
+struct Spans {
+    keys: BTreeMap<String, Spanned<Spans>>,
+    items: Vec<Spanned<Spans>>,
+}
+
+impl Spans {
+    /// Returns the position of the value at flattened key `k`, if known.
+    fn position(buf: &[u8], k: &str) -> Option<Position> {
+        let spans: Spanned<Spans> = toml::from_slice(buf).ok()?;
+        let span = k.split('.').try_fold(&spans, |spans, k| {
+            let spans = spans.get_ref();
+            spans
+                .keys
+                .get(k)
+                .or_else(|| k.parse().ok().and_then(|i: usize| spans.items.get(i)))
+        })?;
+        (span.end() > 0).then(|| Position::from_offset(buf, span.start()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Spans {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SpansVisitor;
+
+        impl<'de> Visitor<'de> for SpansVisitor {
+            type Value = Spans;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a TOML value")
+            }
+
+            fn visit_bool<E>(self, _: bool) -> Result<Self::Value, E> {
+                Ok(Spans::default())
+            }
+
+            fn visit_i64<E>(self, _: i64) -> Result<Self::Value, E> {
+                Ok(Spans::default())
+            }
+
+            fn visit_u64<E>(self, _: u64) -> Result<Self::Value, E> {
+                Ok(Spans::default())
+            }
+
+            fn visit_f64<E>(self, _: f64) -> Result<Self::Value, E> {
+                Ok(Spans::default())
+            }
+
+            fn visit_str<E>(self, _: &str) -> Result<Self::Value, E> {
+                Ok(Spans::default())
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut items = vec![];
+                while let Some(v) = seq.next_element()? {
+                    items.push(v);
+                }
+                Ok(Spans {
+                    items,
+                    ..Default::default()
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut keys = BTreeMap::new();
+                while let Some(k) = map.next_key::<String>()? {
+                    // Datetimes are represented by `toml` as a table with a single private key.
+                    if k == "$__toml_private_datetime" {
+                        let _: IgnoredAny = map.next_value()?;
+                    } else {
+                        let _ = keys.insert(k, map.next_value()?);
+                    }
+                }
+                Ok(Spans {
+                    keys,
+                    ..Default::default()
+                })
+            }
+        }
+
+        deserializer.deserialize_any(SpansVisitor)
+    }
+}
+
+/// Returns the position of the value at flattened key `k` of table at `root` within `src`.
+fn position(src: Option<&[u8]>, root: Option<&str>, k: &str) -> Option<Position> {
+    src.and_then(|src| match root {
+        None => Spans::position(src, k),
+        Some(root) => Spans::position(src, &format!("{root}.{k}")),
+    })
+}
+
+/// Converts an error produced for a TOML document `src` into an [`io::Error`].
+///
+/// If `e` is an [`Error`], its position is looked up in `src`, otherwise `e` is formatted
+/// prefixed by `context`.
+fn invalid_data(
+    src: Option<&[u8]>,
+    root: Option<&str>,
+    context: &str,
+    e: anyhow::Error,
+) -> io::Error {
+    match e.downcast::<Error>() {
+        Ok(mut e) => {
+            e.position = position(src, root, &e.key);
+            io::Error::new(io::ErrorKind::InvalidData, e)
+        }
+        Err(e) => io::Error::new(io::ErrorKind::InvalidData, format!("{context}: {e}")),
     }
 }
 
 /// [TOML](https://toml.io/) configuration file format.
 ///
-/// This format expects the configuration to be represented as a table. Nested tables are not
-/// supported.
+/// This format expects the configuration to be represented as a table. Nested tables are
+/// flattened into dot-separated keys, e.g. `[server] port = 8080` is rendered as
+/// `--server.port=8080`. Tables contained in arrays are flattened using their index within the
+/// array as an additional key segment, e.g. `plugins = [{ name = "a" }]` is rendered as
+/// `--plugins.0.name=a`.
 ///
 /// # Examples
 ///
@@ -112,88 +982,523 @@ fn parse_arg<'a>(
 pub struct Config;
 
 impl Config {
-    fn from_iter(iter: impl IntoIterator<Item = (String, Value)>) -> Result<Vec<String>> {
-        iter.into_iter().try_fold(vec![], |mut args, (k, v)| {
-            for arg in parse_arg(&k, v)? {
-                args.push(arg?);
+    /// Parses `iter` into a [`Vec`] of arguments.
+    ///
+    /// If `skipped` is set, values, which cannot be rendered, are recorded in it instead of
+    /// causing an error.
+    fn from_iter(
+        iter: impl IntoIterator<Item = (String, Value)>,
+        opts: &Options,
+        mut skipped: Option<&mut Vec<Error>>,
+    ) -> Result<Vec<String>> {
+        let mut skip = |e: anyhow::Error| match (skipped.as_mut(), e.downcast::<Error>()) {
+            (Some(skipped), Ok(e)) => {
+                event!(debug, key = e.key, error = %e, "skipping field");
+                skipped.push(e);
+                Ok(())
             }
-            Ok(args)
-        })
+            (None, Ok(e)) => Err(e.into()),
+            (_, Err(e)) => Err(e),
+        };
+        let iter: Vec<_> = iter.into_iter().collect();
+        let defaults: Vec<_> = opts
+            .defaults
+            .iter()
+            .filter(|(k, _)| !opts.omit_defaults && !iter.iter().any(|(sk, _)| sk == *k))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let mut args = vec![];
+        for (k, v) in iter
+            .into_iter()
+            .filter(|(k, v)| !opts.omit_defaults || opts.defaults.get(k) != Some(v))
+            .chain(defaults)
+        {
+            event!(trace, key = k, value = %v, "rendering field");
+            // Primitive values are rendered directly, which avoids boxing an iterator per key.
+            let (arg, vs) = match v {
+                Value::Array(_) | Value::Table(_) => match parse_arg(&k, v, opts) {
+                    Ok(vs) => (None, Some(vs)),
+                    Err(e) => {
+                        skip(e)?;
+                        continue;
+                    }
+                },
+                v => (
+                    check_kind(&k, &v, opts)
+                        .and_then(|()| parse_primitive_arg(&k, v, opts))
+                        .transpose(),
+                    None,
+                ),
+            };
+            for arg in arg.into_iter().chain(vs.into_iter().flatten()) {
+                let arg = match arg {
+                    Ok(arg) => arg,
+                    Err(e) => {
+                        skip(e)?;
+                        continue;
+                    }
+                };
+                if let Some(max) = opts.max_arg_len.filter(|max| arg.len() > *max) {
+                    let k = match &arg {
+                        Arg::Flag { key, .. } => key,
+                        Arg::Named { .. } | Arg::Raw(_) => &k,
+                    };
+                    skip(
+                        Error::new(
+                            k,
+                            format!(
+                                "argument for field `{k}` of {} bytes exceeds maximum length of {max} bytes",
+                                arg.len()
+                            ),
+                        )
+                        .into(),
+                    )?;
+                    continue;
+                }
+                let (name, key, value) = match arg {
+                    Arg::Flag { key, name, value } => {
+                        (flag_name(name.as_deref().unwrap_or(&key), opts), key, value)
+                    }
+                    Arg::Named { name, value } => (name, k.clone(), value),
+                    Arg::Raw(arg) => {
+                        check_max_args(args.len() + 1, &k, opts)?;
+                        args.push(arg);
+                        continue;
+                    }
+                };
+                let separate = !opts.env_vars
+                    && match value.as_deref() {
+                        Some(_) if opts.separate_value.is_some_and(|f| f(&key)) => true,
+                        Some(v) => match opts.separator {
+                            Separator::Equals => false,
+                            Separator::Space => true,
+                            Separator::Smart => !v.starts_with('-'),
+                        },
+                        None => false,
+                    };
+                check_max_args(args.len() + if separate { 2 } else { 1 }, &key, opts)?;
+                if opts.env_vars {
+                    let name: String = name
+                        .chars()
+                        .map(|c| match c {
+                            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+                            _ => '_',
+                        })
+                        .collect();
+                    args.push(format!("{name}={}", value.as_deref().unwrap_or("true")));
+                } else {
+                    match value {
+                        Some(v) if separate => {
+                            args.push(format!("--{name}"));
+                            args.push(v);
+                        }
+                        Some(v) => args.push(format!("--{name}={v}")),
+                        None => args.push(format!("--{name}")),
+                    }
+                }
+            }
+        }
+        Ok(args)
     }
-}
 
-impl Format for Config {
-    type IntoIter = Vec<String>;
+    /// Parses configuration in `buf` and returns a [`Vec`] of arguments.
+    ///
+    /// This is equivalent to [`Format::from_slice`], but does not require type inference for
+    /// `buf`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use confargs::Toml;
+    ///
+    /// let buf: Vec<u8> = r#"string = "foo""#.into();
+    /// assert_eq!(Toml::from_bytes(&buf).unwrap(), vec!["--string=foo"]);
+    /// ```
+    pub fn from_bytes(buf: &[u8]) -> io::Result<Vec<String>> {
+        <Self as Format>::from_slice(buf)
+    }
 
-    fn from_slice(buf: impl AsRef<[u8]>) -> io::Result<Self::IntoIter> {
-        match toml::from_slice(buf.as_ref()).map_err(|e| {
+    /// Parses configuration in `buf` into a TOML value without generating arguments.
+    ///
+    /// The value, which is always a table, can be inspected or modified and converted into
+    /// arguments using [`Self::from_table`] or [`Self::from_table_with`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use confargs::Toml;
+    /// use toml::Value;
+    ///
+    /// let mut value = Toml::parse_value(r#"string = "foo""#).unwrap();
+    /// let kv = value.as_table_mut().unwrap();
+    /// let _ = kv.insert("integer".into(), Value::Integer(42));
+    /// assert_eq!(
+    ///     Toml::from_table(kv.clone()).unwrap(),
+    ///     vec!["--integer=42", "--string=foo"]
+    /// );
+    /// ```
+    pub fn parse_value(buf: impl AsRef<[u8]>) -> io::Result<Value> {
+        Self::parse_document(strip_bom(buf.as_ref())).map(Value::Table)
+    }
+
+    /// Parses an already parsed TOML table `kv` into a [`Vec`] of arguments.
+    ///
+    /// This is useful for generating arguments from a section of a larger document.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use confargs::Toml;
+    ///
+    /// let doc: toml::Value = toml::from_str(
+    ///     r#"[other]
+    /// key = "value"
+    ///
+    /// [mytool]
+    /// port = 8080"#,
+    /// )
+    /// .unwrap();
+    /// let kv = doc["mytool"].as_table().unwrap().clone();
+    /// assert_eq!(Toml::from_table(kv).unwrap(), vec!["--port=8080"]);
+    /// ```
+    pub fn from_table(kv: Table) -> io::Result<Vec<String>> {
+        Self::from_table_with(kv, &Options::default())
+    }
+
+    /// Parses an already parsed TOML table `kv` using [`Options`] into a [`Vec`] of arguments.
+    ///
+    /// Since the source of `kv` is not known, errors do not contain positions.
+    pub fn from_table_with(kv: Table, opts: &Options) -> io::Result<Vec<String>> {
+        Self::from_table_src(kv, opts, None, None)
+    }
+
+    /// Appends `trailing` arguments to `args`, see [`Options::trailing_key`].
+    fn extend_trailing(
+        args: &mut Vec<String>,
+        trailing: Option<Vec<String>>,
+        opts: &Options,
+    ) -> Result<()> {
+        if let (Some(k), Some(trailing)) = (opts.trailing_key.as_deref(), trailing) {
+            check_max_args(args.len() + trailing.len(), k, opts)?;
+            args.extend(trailing);
+        }
+        Ok(())
+    }
+
+    /// Parses table `kv` using [`Options`] and returns a [`Vec`] of arguments.
+    ///
+    /// `src` is the TOML source `kv` was parsed from, if any, and is used to determine positions
+    /// of values in errors. If `skipped` is set, values, which cannot be rendered, are recorded
+    /// in it instead of causing an error.
+    fn from_table_src(
+        kv: Table,
+        opts: &Options,
+        src: Option<&[u8]>,
+        mut skipped: Option<&mut Vec<Error>>,
+    ) -> io::Result<Vec<String>> {
+        event!(
+            debug,
+            keys = kv.len(),
+            root = opts.root,
+            profile = opts.profile,
+            "parsed configuration"
+        );
+        let root = match (opts.root.as_deref(), opts.profile.as_deref()) {
+            (root, None) => root.map(Cow::Borrowed),
+            (None, Some(profile)) => Some(Cow::Owned(format!("profile.{profile}"))),
+            (Some(root), Some(profile)) => Some(Cow::Owned(format!("{root}.profile.{profile}"))),
+        };
+        let root = root.as_deref();
+        let mut kv = match root {
+            None => kv,
+            Some(root) => select_root(kv, root).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("failed to select root table: {e}"),
+                )
+            })?,
+        };
+        if opts.lowercase_keys {
+            kv = lowercase_keys(None, kv)
+                .map_err(|e| invalid_data(src, root, "failed to convert keys", e))?;
+        }
+        if let Some(k) = [opts.trailing_key.as_deref(), opts.condition_key.as_deref()]
+            .into_iter()
+            .flatten()
+            .find(|k| {
+                opts.known_keys
+                    .as_ref()
+                    .is_some_and(|known| known.contains(*k))
+                    || opts.defaults.contains_key(*k)
+            })
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("reserved key `{k}` collides with a regular key"),
+            ));
+        }
+        if opts.env_vars {
+            if let Some(opt) = [
+                (opts.array_flag == ArrayFlag::Once).then_some("array_flag"),
+                opts.numeric_positionals.then_some("numeric_positionals"),
+                opts.trailing_key.is_some().then_some("trailing_key"),
+            ]
+            .into_iter()
+            .flatten()
+            .next()
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("`env_vars` cannot be combined with `{opt}` producing bare arguments"),
+                ));
+            }
+        }
+        let trailing = match opts.trailing_key.as_deref() {
+            Some(k) => kv
+                .remove(k)
+                .map(|v| parse_trailing(k, v))
+                .transpose()
+                .map_err(|e| invalid_data(src, root, "failed to parse trailing arguments", e))?,
+            None => None,
+        };
+        if let Some(k) = opts.condition_key.as_deref() {
+            if let Some(v) = kv.remove(k) {
+                apply_conditions(k, v, &mut kv, opts).map_err(|e| {
+                    invalid_data(src, root, "failed to apply conditional sections", e)
+                })?;
+            }
+        }
+        if let Some(known) = opts.known_keys.as_ref() {
+            check_keys(None, &kv, known)
+                .map_err(|e| invalid_data(src, root, "failed to validate table", e))?;
+        }
+        if opts.interpolate {
+            Interpolator::new(&kv)
+                .interpolate_table(None, &mut kv)
+                .map_err(|e| invalid_data(src, root, "failed to interpolate table", e))?;
+        }
+        if opts.numeric_positionals {
+            if let Some(vs) = numeric_positionals(&kv) {
+                let mut args = vs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (_, k, v))| {
+                        check_max_args(i + 1, k, opts)?;
+                        parse_value(k, v)
+                    })
+                    .collect::<Result<Vec<_>>>()
+                    .map_err(|e| invalid_data(src, root, "failed to parse positionals", e))?;
+                Self::extend_trailing(&mut args, trailing, opts).map_err(|e| {
+                    invalid_data(src, root, "failed to parse trailing arguments", e)
+                })?;
+                return Ok(args);
+            }
+        }
+        let mut args = Self::from_iter(kv, opts, skipped.as_deref_mut())
+            .map_err(|e| invalid_data(src, root, "failed to parse table", e))?;
+        Self::extend_trailing(&mut args, trailing, opts)
+            .map_err(|e| invalid_data(src, root, "failed to parse trailing arguments", e))?;
+        if let Some(skipped) = skipped {
+            skipped
+                .iter_mut()
+                .for_each(|e| e.position = position(src, root, &e.key));
+        }
+        Ok(args)
+    }
+
+    /// Parses TOML document `buf` into a table.
+    fn parse_document(buf: &[u8]) -> io::Result<Table> {
+        match toml::from_slice(buf).map_err(|e| {
             io::Error::new(
                 io::ErrorKind::InvalidData,
-                format!("failed to parse TOML: {e}"),
+                format!("failed to parse TOML: {}", describe_parse_error(buf, e)),
             )
         })? {
-            Value::Table(kv) => Self::from_iter(kv).map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("failed to parse TOML table: {e}"),
-                )
-            }),
+            Value::Table(kv) => Ok(kv),
             _ => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "invalid config file format",
             )),
         }
     }
+
+    /// Parses configuration in `buf` using [`Options`] and returns a [`Vec`] of arguments for
+    /// each top-level table in order of their names along with the table name, e.g. to run a
+    /// sequence of subcommands each with its own arguments.
+    ///
+    /// Each table is rendered as if it was selected by [`Options::root`]. If
+    /// [`Options::root`] is set, tables contained in the root table are rendered instead. It is
+    /// an error for the configuration to contain values, which are not tables, at the top level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use confargs::{Options, Toml};
+    ///
+    /// assert_eq!(
+    ///     Toml::from_slice_tables_with(
+    ///         r#"[build]
+    /// release = true
+    ///
+    /// [test]
+    /// jobs = 4"#,
+    ///         &Options::default(),
+    ///     )
+    ///     .unwrap(),
+    ///     vec![
+    ///         ("build".into(), vec!["--release".into()]),
+    ///         ("test".into(), vec!["--jobs=4".into()]),
+    ///     ],
+    /// );
+    /// ```
+    pub fn from_slice_tables_with(
+        buf: impl AsRef<[u8]>,
+        opts: &Options,
+    ) -> io::Result<Vec<(String, Vec<String>)>> {
+        let buf = strip_bom(buf.as_ref());
+        let kv = Self::parse_document(buf)?;
+        let root = opts.root.as_deref();
+        let names: Vec<_> = match root {
+            None => kv.keys().cloned().collect(),
+            Some(root) => select_root(kv.clone(), root)
+                .map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("failed to select root table: {e}"),
+                    )
+                })?
+                .keys()
+                .cloned()
+                .collect(),
+        };
+        names
+            .into_iter()
+            .map(|name| {
+                let opts = Options {
+                    root: Some(root.map_or_else(|| name.clone(), |root| format!("{root}.{name}"))),
+                    ..opts.clone()
+                };
+                let args = Self::from_table_src(kv.clone(), &opts, Some(buf), None)?;
+                Ok((name, args))
+            })
+            .collect()
+    }
+
+    /// Parses `buf` using [`Options`] into a [`Vec`] of arguments, skipping values, which cannot
+    /// be rendered, e.g. nested arrays, instead of returning an error.
+    ///
+    /// Skipped values are returned as [`Error`]s, which contain the key and the reason. Note,
+    /// that other errors, e.g. parsing errors or unknown keys, are still returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use confargs::{Options, Toml};
+    ///
+    /// let (args, skipped) = Toml::from_slice_lenient(
+    ///     r#"string = "foo"
+    /// nested = [[1, 2]]"#,
+    ///     &Options::default(),
+    /// )
+    /// .unwrap();
+    /// assert_eq!(args, vec!["--string=foo"]);
+    /// assert_eq!(skipped.len(), 1);
+    /// assert_eq!(skipped[0].key, "nested.0");
+    /// eprintln!("ignored {} unsupported config fields", skipped.len());
+    /// ```
+    pub fn from_slice_lenient(
+        buf: impl AsRef<[u8]>,
+        opts: &Options,
+    ) -> io::Result<(Vec<String>, Vec<Error>)> {
+        let buf = strip_bom(buf.as_ref());
+        let mut skipped = vec![];
+        let args = Self::from_table_src(
+            Self::parse_document(buf)?,
+            opts,
+            Some(buf),
+            Some(&mut skipped),
+        )?;
+        Ok((args, skipped))
+    }
+}
+
+impl Format for Config {
+    type IntoIter = Vec<String>;
+
+    fn extensions() -> &'static [&'static str] {
+        &["toml"]
+    }
+
+    fn from_slice(buf: impl AsRef<[u8]>) -> io::Result<Self::IntoIter> {
+        Self::from_slice_with(buf, &Options::default())
+    }
+
+    fn from_slice_with(buf: impl AsRef<[u8]>, opts: &Options) -> io::Result<Self::IntoIter> {
+        let buf = strip_bom(buf.as_ref());
+        Self::from_table_src(Self::parse_document(buf)?, opts, Some(buf), None)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use toml::value::{Datetime, Time};
+    use crate::{KebabCase, NamingStrategy, Prefixed};
+
+    use std::sync::Arc;
+
+    use toml::value::Time;
 
     #[test]
     fn parse_arg() {
         assert_eq!(
-            super::parse_arg("key", Value::String("foo".into()))
+            super::parse_arg("key", Value::String("foo".into()), &Options::default())
                 .unwrap()
+                .map(|arg| arg.map(|arg| arg.to_string()))
                 .collect::<Result<Vec<_>>>()
                 .unwrap(),
             vec!["--key=foo"]
         );
         assert_eq!(
-            super::parse_arg("key", Value::Integer(42))
+            super::parse_arg("key", Value::Integer(42), &Options::default())
                 .unwrap()
+                .map(|arg| arg.map(|arg| arg.to_string()))
                 .collect::<Result<Vec<_>>>()
                 .unwrap(),
             vec!["--key=42"]
         );
         assert_eq!(
-            super::parse_arg("key", Value::Float(42.))
+            super::parse_arg("key", Value::Float(42.), &Options::default())
                 .unwrap()
+                .map(|arg| arg.map(|arg| arg.to_string()))
                 .collect::<Result<Vec<_>>>()
                 .unwrap(),
             vec!["--key=42"]
         );
         assert_eq!(
-            super::parse_arg("key", Value::Float(42.2))
+            super::parse_arg("key", Value::Float(42.2), &Options::default())
                 .unwrap()
+                .map(|arg| arg.map(|arg| arg.to_string()))
                 .collect::<Result<Vec<_>>>()
                 .unwrap(),
             vec!["--key=42.2"]
         );
         assert_eq!(
-            super::parse_arg("key", Value::Boolean(true))
+            super::parse_arg("key", Value::Boolean(true), &Options::default())
                 .unwrap()
+                .map(|arg| arg.map(|arg| arg.to_string()))
                 .collect::<Result<Vec<_>>>()
                 .unwrap(),
             vec!["--key"]
         );
-        assert!(super::parse_arg("key", Value::Boolean(false))
-            .unwrap()
-            .collect::<Result<Vec<_>>>()
-            .unwrap()
-            .is_empty());
+        assert!(
+            super::parse_arg("key", Value::Boolean(false), &Options::default())
+                .unwrap()
+                .map(|arg| arg.map(|arg| arg.to_string()))
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+                .is_empty()
+        );
         assert_eq!(
             super::parse_arg(
                 "key",
@@ -206,9 +1511,11 @@ mod tests {
                         nanosecond: 4,
                     }),
                     offset: None,
-                })
+                }),
+                &Options::default(),
             )
             .unwrap()
+            .map(|arg| arg.map(|arg| arg.to_string()))
             .collect::<Result<Vec<_>>>()
             .unwrap(),
             vec!["--key=01:02:03.000000004"]
@@ -221,12 +1528,1837 @@ mod tests {
                     Value::Boolean(false),
                     Value::Integer(42),
                     Value::String("test".into())
-                ])
+                ]),
+                &Options::default(),
             )
             .unwrap()
+            .map(|arg| arg.map(|arg| arg.to_string()))
             .collect::<Result<Vec<_>>>()
             .unwrap(),
             vec!["--key", "--key=42", "--key=test"]
         );
+        assert_eq!(
+            super::parse_arg(
+                "key",
+                Value::Array(vec![
+                    Value::Boolean(true),
+                    Value::Boolean(false),
+                    Value::Integer(42),
+                    Value::String("test".into())
+                ]),
+                &Options {
+                    array_bool: ArrayBool::Value,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .map(|arg| arg.map(|arg| arg.to_string()))
+            .collect::<Result<Vec<_>>>()
+            .unwrap(),
+            vec!["--key=true", "--key=false", "--key=42", "--key=test"]
+        );
+    }
+
+    #[test]
+    fn parse_array_bool_count() {
+        let opts = Options {
+            array_bool: ArrayBool::Count,
+            negatable_keys: HashSet::from(["flag".into()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with("flag = [true, true]", &opts).unwrap(),
+            vec!["--flag", "--flag"]
+        );
+        assert_eq!(
+            Config::from_slice_with("flag = [true, false, true, true, 1]", &opts).unwrap(),
+            vec!["--flag", "--flag", "--flag=1"]
+        );
+        assert!(Config::from_slice_with("flag = [true, false]", &opts)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_table() {
+        assert_eq!(
+            super::parse_arg(
+                "server",
+                Value::Table(Table::from_iter([
+                    ("host".into(), Value::String("localhost".into())),
+                    ("port".into(), Value::Integer(8080)),
+                    (
+                        "tls".into(),
+                        Value::Table(Table::from_iter([("enabled".into(), Value::Boolean(true))]))
+                    ),
+                ])),
+                &Options::default(),
+            )
+            .unwrap()
+            .map(|arg| arg.map(|arg| arg.to_string()))
+            .collect::<Result<Vec<_>>>()
+            .unwrap(),
+            vec![
+                "--server.host=localhost",
+                "--server.port=8080",
+                "--server.tls.enabled"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_table_array() {
+        assert_eq!(
+            Config::from_slice(
+                r#"plugins = [{ name = "a", enabled = true }, { name = "b" }]
+other = [{ name = "c" }]"#
+            )
+            .unwrap(),
+            vec![
+                "--other.0.name=c",
+                "--plugins.0.enabled",
+                "--plugins.0.name=a",
+                "--plugins.1.name=b",
+            ]
+        );
+    }
+
+    #[test]
+    fn table_array_grouped() {
+        let opts = Options {
+            table_array: TableArray::Grouped { separator: '-' },
+            negatable_keys: HashSet::from(["endpoint.tls".into()]),
+            value_aliases: HashMap::from([(
+                "endpoint.mode".into(),
+                HashMap::from([("fast".into(), "1".into())]),
+            )]),
+            integer_ranges: HashMap::from([("endpoint.retries.count".into(), 0..=5)]),
+            naming: Some(Arc::new(KebabCase)),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"[[endpoint]]
+mode = "fast"
+tls = false
+dataDir = "/a"
+retries = [{ count = 1 }]
+"#,
+                &opts
+            )
+            .unwrap(),
+            vec![
+                "--endpoint-data-dir=/a",
+                "--endpoint-mode=1",
+                "--endpoint-retries-count=1",
+                "--endpoint-tls=false",
+            ]
+        );
+        let err = Config::from_slice_with("endpoint = [{ retries = [{ count = 6 }] }]", &opts)
+            .unwrap_err();
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<Error>())
+            .unwrap();
+        assert_eq!(err.key, "endpoint.retries.count");
+    }
+
+    #[test]
+    fn root() {
+        const CONFIG: &str = r#"[mytool]
+string = "foo"
+integer = 42
+
+[othertool]
+string = "bar"
+"#;
+
+        let root = |root: &str| Options {
+            root: Some(root.into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &root("mytool")).unwrap(),
+            vec!["--integer=42", "--string=foo"]
+        );
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &root("othertool")).unwrap(),
+            vec!["--string=bar"]
+        );
+        assert!(Config::from_slice_with(CONFIG, &root("missing")).is_err());
+        assert!(Config::from_slice_with("mytool = 42", &root("mytool")).is_err());
+    }
+
+    #[test]
+    fn from_slice_tables_with() {
+        const CONFIG: &str = r#"[test]
+jobs = 4
+
+[build]
+release = true
+target = "x86_64"
+
+[tool.mytool.a]
+string = "foo"
+
+[tool.mytool.b]
+"#;
+
+        assert_eq!(
+            Config::from_slice_tables_with(
+                r#"[test]
+jobs = 4
+
+[build]
+release = true
+target = "x86_64"
+"#,
+                &Options::default()
+            )
+            .unwrap(),
+            vec![
+                (
+                    "build".into(),
+                    vec!["--release".into(), "--target=x86_64".into()]
+                ),
+                ("test".into(), vec!["--jobs=4".into()]),
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_tables_with(
+                CONFIG,
+                &Options {
+                    root: Some("tool.mytool".into()),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec![
+                ("a".into(), vec!["--string=foo".into()]),
+                ("b".into(), vec![]),
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_tables_with("jobs = 4\n[build]", &Options::default())
+                .unwrap_err()
+                .to_string(),
+            "failed to select root table: `jobs` is not a table"
+        );
+    }
+
+    #[test]
+    fn bool_tables() {
+        let opts = Options {
+            bool_tables: HashMap::from([(
+                "features".into(),
+                BoolTable {
+                    flag: "enable".into(),
+                    delimiter: " ".into(),
+                },
+            )]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"[features]
+foo = true
+bar = false
+baz = true
+"#,
+                &opts
+            )
+            .unwrap(),
+            vec!["--enable=baz foo"]
+        );
+        assert_eq!(
+            Config::from_slice_with("features = { foo = false }", &opts).unwrap(),
+            Vec::<String>::new()
+        );
+        let err = Config::from_slice_with("features = { foo = 1 }", &opts).unwrap_err();
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<Error>())
+            .unwrap();
+        assert_eq!(err.key, "features.foo");
+        assert_eq!(err.kind, Some(ValueKind::Integer));
+    }
+
+    #[test]
+    fn integer_ranges() {
+        let opts = Options {
+            integer_ranges: HashMap::from([("port".into(), 1..=65535), ("ids".into(), 0..=9)]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with("port = 65535\nids = [0, 9]\nother = 99999", &opts).unwrap(),
+            vec!["--ids=0", "--ids=9", "--other=99999", "--port=65535"]
+        );
+        for (config, key, msg) in [
+            (
+                "port = 99999",
+                "port",
+                "integer `99999` of field `port` is out of range `1..=65535`",
+            ),
+            (
+                "ids = [1, -1]",
+                "ids",
+                "integer `-1` of field `ids` is out of range `0..=9`",
+            ),
+        ] {
+            let err = Config::from_slice_with(config, &opts).unwrap_err();
+            let err = err
+                .get_ref()
+                .and_then(|e| e.downcast_ref::<Error>())
+                .unwrap();
+            assert_eq!(err.key, key);
+            assert_eq!(err.message, msg);
+            assert_eq!(err.kind, Some(ValueKind::Integer));
+        }
+    }
+
+    #[test]
+    fn value_aliases() {
+        let opts = Options {
+            value_aliases: HashMap::from([
+                (
+                    "level".into(),
+                    HashMap::from([("warn".into(), "2".into()), ("error".into(), "3".into())]),
+                ),
+                (
+                    "color".into(),
+                    HashMap::from([("yes".into(), "always".into())]),
+                ),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"level = "warn"
+color = ["yes", "auto"]
+other = "warn"
+"#,
+                &opts
+            )
+            .unwrap(),
+            vec![
+                "--color=always",
+                "--color=auto",
+                "--level=2",
+                "--other=warn"
+            ]
+        );
+    }
+
+    #[test]
+    fn array_flag_once() {
+        let opts = Options {
+            array_flag: ArrayFlag::Once,
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"array = ["c", "a", "b"]
+mixed = [1, true, 2.5]
+string = "foo"
+empty = []
+"#,
+                &opts
+            )
+            .unwrap(),
+            vec![
+                "--array",
+                "c",
+                "a",
+                "b",
+                "--mixed",
+                "1",
+                "true",
+                "2.5",
+                "--string=foo"
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(
+                r#"extra_args = ["--max_retries=3", "-v"]"#,
+                &Options {
+                    array_flag: ArrayFlag::Once,
+                    flag_prefix: Some("app-".into()),
+                    naming: Some(Arc::new(KebabCase)),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec!["--app-extra-args", "--max_retries=3", "-v"]
+        );
+        let err = Config::from_slice_with("array = [{ a = 1 }]", &opts).unwrap_err();
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<Error>())
+            .unwrap();
+        assert_eq!(err.key, "array.0");
+        assert_eq!(err.kind, Some(ValueKind::Table));
+    }
+
+    #[test]
+    fn utc_datetimes() {
+        let opts = Options {
+            utc_datetimes: true,
+            whole_second_keys: HashSet::from(["whole".into()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"offset = 2024-03-01T01:30:00+02:00
+negative = 2023-12-31T23:30:00.5-01:15
+whole = 1970-01-01T00:00:00.25+00:01
+utc = 2024-01-01T00:00:00Z
+local = 2024-01-01T00:00:00
+date = 2024-01-01
+"#,
+                &opts
+            )
+            .unwrap(),
+            vec![
+                "--date=2024-01-01",
+                "--local=2024-01-01T00:00:00",
+                "--negative=2024-01-01T00:45:00.5Z",
+                "--offset=2024-02-29T23:30:00Z",
+                "--utc=2024-01-01T00:00:00Z",
+                "--whole=1969-12-31T23:59:00Z",
+            ]
+        );
+        assert_eq!(
+            Config::from_slice("offset = 2024-03-01T01:30:00+02:00").unwrap(),
+            vec!["--offset=2024-03-01T01:30:00+02:00"]
+        );
+        assert_eq!(
+            Config::from_slice_with("min = 0000-01-01T01:00:00+01:00", &opts).unwrap(),
+            vec!["--min=0000-01-01T00:00:00Z"]
+        );
+        for config in [
+            "min = 0000-01-01T00:00:00+01:00",
+            "max = 9999-12-31T23:00:00-01:00",
+        ] {
+            let err = Config::from_slice_with(config, &opts).unwrap_err();
+            let err = err
+                .get_ref()
+                .and_then(|e| e.downcast_ref::<Error>())
+                .unwrap();
+            assert_eq!(err.kind, Some(ValueKind::Datetime), "config: {config}");
+        }
+    }
+
+    #[test]
+    fn profile() {
+        const CONFIG: &str = r#"[profile.dev]
+debug = true
+opt-level = 0
+
+[profile.release]
+opt-level = 3
+
+[tool.mytool.profile.release]
+lto = true
+"#;
+
+        let profile = |root: Option<&str>, profile: &str| Options {
+            root: root.map(Into::into),
+            profile: Some(profile.into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &profile(None, "release")).unwrap(),
+            vec!["--opt-level=3"]
+        );
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &profile(None, "dev")).unwrap(),
+            vec!["--debug", "--opt-level=0"]
+        );
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &profile(Some("tool.mytool"), "release")).unwrap(),
+            vec!["--lto"]
+        );
+        assert!(Config::from_slice_with(CONFIG, &profile(None, "bench")).is_err());
+    }
+
+    #[test]
+    fn known_keys() {
+        const CONFIG: &str = r#"string = "foo"
+plugins = [{ name = "a" }]
+
+[server]
+port = 8080
+tls = { enabled = true }
+"#;
+
+        let known = |keys: &[&str]| Options {
+            known_keys: Some(keys.iter().map(|&k| k.into()).collect()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                CONFIG,
+                &known(&["string", "plugins.0.name", "server.port", "server.tls"])
+            )
+            .unwrap(),
+            vec![
+                "--plugins.0.name=a",
+                "--server.port=8080",
+                "--server.tls.enabled",
+                "--string=foo",
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &known(&["string", "plugins", "server"])).unwrap(),
+            vec![
+                "--plugins.0.name=a",
+                "--server.port=8080",
+                "--server.tls.enabled",
+                "--string=foo",
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(r#"stirng = "foo""#, &known(&["string"]))
+                .unwrap_err()
+                .to_string(),
+            "unknown key `stirng` at line 1, column 10"
+        );
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &known(&["string", "plugins", "server.port"]))
+                .unwrap_err()
+                .to_string(),
+            "unknown key `server.tls.enabled` at line 6, column 19"
+        );
+    }
+
+    #[test]
+    fn error_position() {
+        let err = |buf: &str, opts: &Options| {
+            Config::from_slice_with(buf, opts)
+                .unwrap_err()
+                .into_inner()
+                .unwrap()
+                .downcast::<Error>()
+                .unwrap()
+        };
+
+        assert_eq!(
+            *err(
+                r#"string = "foo"
+
+[server]
+ports = [8080, [8081, 8082]]
+"#,
+                &Options::default()
+            ),
+            Error {
+                key: "server.ports.1".into(),
+                message: "nested array at index 1 of field `server.ports` not supported".into(),
+                position: Some(Position {
+                    line: 4,
+                    column: 16
+                }),
+                kind: Some(ValueKind::Array),
+            }
+        );
+        assert_eq!(
+            err(
+                r#"[mytool]
+  plugins = [{ name = "a" }, { name = "b", stirng = "foo" }]
+"#,
+                &Options {
+                    root: Some("mytool".into()),
+                    known_keys: Some(HashSet::from(["plugins.0".into(), "plugins.1.name".into()])),
+                    ..Default::default()
+                }
+            )
+            .position,
+            Some(Position {
+                line: 2,
+                column: 53
+            }),
+        );
+    }
+
+    #[test]
+    fn unix_timestamp() {
+        let opts = Options {
+            unix_timestamp_keys: HashSet::from(["since".into(), "until".into()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"since = 2024-01-01T02:00:00+02:00
+until = 1969-12-31T19:00:00.5-05:00
+date = 2024-01-01"#,
+                &opts
+            )
+            .unwrap(),
+            vec!["--date=2024-01-01", "--since=1704067200", "--until=0"]
+        );
+        assert_eq!(
+            Config::from_slice_with("since = 2024-03-01", &opts).unwrap(),
+            vec!["--since=1709251200"]
+        );
+        assert_eq!(
+            Config::from_slice_with("since = 01:02:03", &opts)
+                .unwrap_err()
+                .to_string(),
+            "Unix timestamp of datetime without date not supported for field `since` at line 1, column 9"
+        );
+    }
+
+    #[test]
+    fn negatable_keys() {
+        assert_eq!(
+            Config::from_slice_with(
+                r#"verbose = false
+color = false
+enabled = true"#,
+                &Options {
+                    negatable_keys: HashSet::from(["verbose".into(), "enabled".into()]),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec!["--enabled", "--verbose=false"]
+        );
+    }
+
+    #[test]
+    fn interpolate() {
+        let opts = Options {
+            interpolate: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"base = "/opt/app"
+data = "{base}/data"
+cache = "{data}/cache"
+port = 8080
+url = "http://{server.host}:{port}"
+literal = "{{base}} }} }"
+paths = ["{base}/a", "{paths.0}/b"]
+
+[server]
+host = "localhost"
+"#,
+                &opts
+            )
+            .unwrap(),
+            vec![
+                "--base=/opt/app",
+                "--cache=/opt/app/data/cache",
+                "--data=/opt/app/data",
+                "--literal={base} } }",
+                "--paths=/opt/app/a",
+                "--paths=/opt/app/a/b",
+                "--port=8080",
+                "--server.host=localhost",
+                "--url=http://localhost:8080",
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(r#"data = "{base}/data""#, &Options::default()).unwrap(),
+            vec!["--data={base}/data"]
+        );
+
+        let err = |buf| Config::from_slice_with(buf, &opts).unwrap_err().to_string();
+        assert_eq!(
+            err(r#"a = "{b}"
+b = "{a}""#),
+            "cyclic reference to field `a` at line 1, column 5"
+        );
+        assert_eq!(
+            err(r#"a = "{a}""#),
+            "cyclic reference to field `a` at line 1, column 5"
+        );
+        assert_eq!(
+            err(r#"a = "{b}""#),
+            "unknown field `b` referenced in field `a` at line 1, column 5"
+        );
+        assert_eq!(
+            err(r#"a = "{b""#),
+            "unterminated reference in field `a` at line 1, column 5"
+        );
+    }
+
+    #[test]
+    fn root_path() {
+        const CONFIG: &str = r#"[tool.mytool]
+string = "foo"
+
+[tool.othertool]
+string = "bar"
+
+[package]
+name = "test"
+"#;
+
+        let root = |root: &str| Options {
+            root: Some(root.into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &root("tool.mytool")).unwrap(),
+            vec!["--string=foo"]
+        );
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &root("tool.missing"))
+                .unwrap_err()
+                .to_string(),
+            "failed to select root table: table `tool.missing` not found"
+        );
+        assert_eq!(
+            Config::from_slice_with(CONFIG, &root("package.name.first"))
+                .unwrap_err()
+                .to_string(),
+            "failed to select root table: `package.name` is not a table"
+        );
+    }
+
+    #[test]
+    fn parse_mixed_array() {
+        assert_eq!(
+            Config::from_slice(r#"x = [1, { a = 2, b = { c = true } }, "foo", { a = 3 }]"#)
+                .unwrap(),
+            vec!["--x=1", "--x.1.a=2", "--x.1.b.c", "--x=foo", "--x.3.a=3"]
+        );
+        assert_eq!(
+            Config::from_slice(r#"x = [1, [2, 3]]"#)
+                .unwrap_err()
+                .to_string(),
+            "nested array at index 1 of field `x` not supported at line 1, column 9"
+        );
+    }
+
+    #[test]
+    fn bom() {
+        assert_eq!(
+            Config::from_slice(b"\xEF\xBB\xBFstring = \"foo\"\nbool = true").unwrap(),
+            vec!["--bool", "--string=foo"]
+        );
+        assert_eq!(
+            Config::from_slice(b"\xEF\xBB\xBFkey = [[1, 2]]")
+                .unwrap_err()
+                .to_string(),
+            "nested array at index 0 of field `key` not supported at line 1, column 8"
+        );
+    }
+
+    #[test]
+    fn numeric_string_keys() {
+        let opts = Options {
+            numeric_string_keys: ["big", "neg", "frac", "name", "bad", "list"]
+                .map(String::from)
+                .into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"big = "1_000_000"
+neg = "-1_000"
+frac = "+1_000.000_1"
+name = "foo_bar"
+bad = "1__000"
+list = ["1_0", "_1"]
+other = "1_000""#,
+                &opts
+            )
+            .unwrap(),
+            vec![
+                "--bad=1__000",
+                "--big=1000000",
+                "--frac=+1000.0001",
+                "--list=10",
+                "--list=_1",
+                "--name=foo_bar",
+                "--neg=-1000",
+                "--other=1_000",
+            ]
+        );
+    }
+
+    #[test]
+    fn lowercase_keys() {
+        let opts = Options {
+            lowercase_keys: true,
+            known_keys: Some(
+                ["max_retries", "server.host", "plugins.0.name"]
+                    .map(String::from)
+                    .into(),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"Max_Retries = 3
+plugins = [{ Name = "a" }]
+
+[Server]
+HOST = "localhost""#,
+                &opts
+            )
+            .unwrap(),
+            vec![
+                "--max_retries=3",
+                "--plugins.0.name=a",
+                "--server.host=localhost"
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(
+                r#"[server]
+Host = "a"
+host = "b""#,
+                &opts
+            )
+            .unwrap_err()
+            .to_string(),
+            "duplicate key `server.host` after converting `host` to lowercase at line 3, column 8"
+        );
+    }
+
+    #[test]
+    fn suffixes() {
+        let config = r#"timeout = 30
+delays = [1.5, 2]
+retries = 3
+name = "foo"
+
+[server]
+timeout = 10"#;
+        assert_eq!(
+            Config::from_slice(config).unwrap(),
+            vec![
+                "--delays=1.5",
+                "--delays=2",
+                "--name=foo",
+                "--retries=3",
+                "--server.timeout=10",
+                "--timeout=30",
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(
+                config,
+                &Options {
+                    suffixes: [
+                        ("timeout", "s"),
+                        ("delays", "ms"),
+                        ("server.timeout", "m"),
+                        ("name", "s")
+                    ]
+                    .map(|(k, v)| (k.into(), v.into()))
+                    .into(),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec![
+                "--delays=1.5ms",
+                "--delays=2ms",
+                "--name=foo",
+                "--retries=3",
+                "--server.timeout=10m",
+                "--timeout=30s",
+            ]
+        );
+    }
+
+    #[test]
+    fn max_arg_len() {
+        let config = r#"name = "foo"
+
+[data]
+blob = "aGVsbG8gd29ybGQ=""#;
+        let opts = |max_arg_len| Options {
+            max_arg_len,
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(config, &opts(None)).unwrap(),
+            vec!["--data.blob=aGVsbG8gd29ybGQ=", "--name=foo"]
+        );
+        assert_eq!(
+            Config::from_slice_with(config, &opts(Some(28))).unwrap(),
+            vec!["--data.blob=aGVsbG8gd29ybGQ=", "--name=foo"]
+        );
+        assert_eq!(
+            Config::from_slice_with(config, &opts(Some(27)))
+                .unwrap_err()
+                .to_string(),
+            "argument for field `data.blob` of 28 bytes exceeds maximum length of 27 bytes at line 4, column 8"
+        );
+    }
+
+    #[test]
+    fn bool_keys() {
+        let opts = Options {
+            bool_keys: ["on", "off", "neg", "bad"].map(String::from).into(),
+            negatable_keys: ["neg"].map(String::from).into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with("on = 1\noff = 0\nneg = 0\nint = 1", &opts).unwrap(),
+            vec!["--int=1", "--neg=false", "--on"]
+        );
+        assert_eq!(
+            Config::from_slice_with("bad = 2", &opts)
+                .unwrap_err()
+                .to_string(),
+            "integer `2` is not a valid boolean for field `bad` at line 1, column 7"
+        );
+    }
+
+    #[test]
+    fn from_table() {
+        let kv = match toml::from_str(
+            r#"[tool.mytool]
+port = 8080
+hosts = ["a", "b"]"#,
+        )
+        .unwrap()
+        {
+            Value::Table(mut kv) => match kv.remove("tool") {
+                Some(Value::Table(mut kv)) => match kv.remove("mytool") {
+                    Some(Value::Table(kv)) => kv,
+                    _ => panic!("`mytool` is not a table"),
+                },
+                _ => panic!("`tool` is not a table"),
+            },
+            _ => panic!("document is not a table"),
+        };
+        assert_eq!(
+            Config::from_table(kv.clone()).unwrap(),
+            vec!["--hosts=a", "--hosts=b", "--port=8080"]
+        );
+        assert_eq!(
+            Config::from_table_with(
+                kv,
+                &Options {
+                    suffixes: [("port".into(), "/tcp".into())].into(),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec!["--hosts=a", "--hosts=b", "--port=8080/tcp"]
+        );
+    }
+
+    #[test]
+    fn from_slice_lenient() {
+        let config = r#"string = "foo"
+nested = [1, [2]]
+time = 01:02:03
+
+[server]
+port = 8080"#;
+        let opts = Options {
+            unix_timestamp_keys: ["time"].map(String::from).into(),
+            ..Default::default()
+        };
+        assert!(Config::from_slice_with(config, &opts).is_err());
+        assert_eq!(
+            Config::from_slice_lenient(config, &opts).unwrap(),
+            (
+                vec![
+                    "--nested=1".into(),
+                    "--server.port=8080".into(),
+                    "--string=foo".into()
+                ],
+                vec![
+                    Error {
+                        key: "nested.1".into(),
+                        message: "nested array at index 1 of field `nested` not supported".into(),
+                        position: Some(Position {
+                            line: 2,
+                            column: 14
+                        }),
+                        kind: Some(ValueKind::Array),
+                    },
+                    Error {
+                        key: "time".into(),
+                        message:
+                            "Unix timestamp of datetime without date not supported for field `time`"
+                                .into(),
+                        position: Some(Position { line: 3, column: 8 }),
+                        kind: Some(ValueKind::Datetime),
+                    },
+                ]
+            )
+        );
+        assert!(Config::from_slice_lenient("string = ", &opts).is_err());
+    }
+
+    #[test]
+    fn separate_value() {
+        assert_eq!(
+            Config::from_slice_with(
+                r#"pattern = "-v"
+name = "foo"
+list = ["-a", "-b"]
+flag = true
+
+[server]
+args = "--x=1""#,
+                &Options {
+                    separate_value: Some(|k| k == "pattern"
+                        || k == "list"
+                        || k.starts_with("server.")
+                        || k == "flag"),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec![
+                "--flag",
+                "--list",
+                "-a",
+                "--list",
+                "-b",
+                "--name=foo",
+                "--pattern",
+                "-v",
+                "--server.args",
+                "--x=1",
+            ]
+        );
+    }
+
+    #[test]
+    fn joined_keys() {
+        let opts = Options {
+            joined_keys: [
+                ("tags", ","),
+                ("server.hosts", ";"),
+                ("empty", ","),
+                ("bad", ","),
+            ]
+            .map(|(k, sep)| (k.into(), sep.into()))
+            .into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"tags = ["a", "b", 1, true]
+ids = [1, 2]
+empty = []
+
+[server]
+hosts = ["x", "y"]"#,
+                &opts
+            )
+            .unwrap(),
+            vec![
+                "--ids=1",
+                "--ids=2",
+                "--server.hosts=x;y",
+                "--tags=a,b,1,true",
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(r#"bad = ["a", { b = 1 }]"#, &opts)
+                .unwrap_err()
+                .to_string(),
+            "table not supported for field `bad.1` at line 1, column 13"
+        );
+    }
+
+    #[test]
+    fn whole_second_keys() {
+        let config = r#"time = 01:02:03.000000004
+whole = 01:02:03
+datetime = 1979-05-27T07:32:00.999999Z
+date = 1979-05-27
+other = 01:02:03.5"#;
+        assert_eq!(
+            Config::from_slice_with(
+                config,
+                &Options {
+                    whole_second_keys: ["time", "whole", "datetime", "date"]
+                        .map(String::from)
+                        .into(),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec![
+                "--date=1979-05-27",
+                "--datetime=1979-05-27T07:32:00Z",
+                "--other=01:02:03.5",
+                "--time=01:02:03",
+                "--whole=01:02:03",
+            ]
+        );
+    }
+
+    #[test]
+    fn max_args() {
+        let config = format!(
+            "name = \"foo\"\nids = [{}]",
+            (0..100)
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let opts = |max_args| Options {
+            max_args,
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(&config, &opts(None)).unwrap().len(),
+            101
+        );
+        assert_eq!(
+            Config::from_slice_with(&config, &opts(Some(101)))
+                .unwrap()
+                .len(),
+            101
+        );
+        assert_eq!(
+            Config::from_slice_with(&config, &opts(Some(100)))
+                .unwrap_err()
+                .to_string(),
+            "number of arguments exceeds maximum of 100 at field `name` at line 1, column 8"
+        );
+        assert_eq!(
+            Config::from_slice_with(&config, &opts(Some(10)))
+                .unwrap_err()
+                .to_string(),
+            "number of arguments exceeds maximum of 10 at field `ids` at line 2, column 7"
+        );
+        let opts = |max_args| Options {
+            max_args: Some(max_args),
+            array_flag: ArrayFlag::Once,
+            numeric_positionals: true,
+            trailing_key: Some("trailing".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with("ids = [1, 2]\ntrailing = [\"-v\"]", &opts(5)).unwrap(),
+            vec!["--ids", "1", "2", "--", "-v"]
+        );
+        assert_eq!(
+            Config::from_slice_with("ids = [1, 2]", &opts(2))
+                .unwrap_err()
+                .to_string(),
+            "number of arguments exceeds maximum of 2 at field `ids` at line 1, column 7"
+        );
+        assert_eq!(
+            Config::from_slice_with("ids = [1, 2]\ntrailing = [\"-v\"]", &opts(4))
+                .unwrap_err()
+                .to_string(),
+            "number of arguments exceeds maximum of 4 at field `trailing` at line 2, column 12"
+        );
+        assert_eq!(
+            Config::from_slice_with("0 = \"a\"\n1 = \"b\"", &opts(1))
+                .unwrap_err()
+                .to_string(),
+            "number of arguments exceeds maximum of 1 at field `1` at line 2, column 5"
+        );
+    }
+
+    #[test]
+    fn error_kind() {
+        let kind = |config: &str, opts: &Options| {
+            Config::from_slice_with(config, opts)
+                .unwrap_err()
+                .get_ref()
+                .and_then(|e| e.downcast_ref::<Error>())
+                .map(|e| e.kind)
+        };
+        let opts = Options::default();
+        assert_eq!(kind("x = [[1]]", &opts), Some(Some(ValueKind::Array)));
+        assert_eq!(
+            kind(
+                "x = 2",
+                &Options {
+                    bool_keys: ["x"].map(String::from).into(),
+                    ..Default::default()
+                }
+            ),
+            Some(Some(ValueKind::Integer))
+        );
+        assert_eq!(
+            kind(
+                "x = [{ a = 1 }]",
+                &Options {
+                    joined_keys: [("x".into(), ",".into())].into(),
+                    ..Default::default()
+                }
+            ),
+            Some(Some(ValueKind::Table))
+        );
+        assert_eq!(
+            kind(
+                "x = 1",
+                &Options {
+                    known_keys: Some(HashSet::new()),
+                    ..Default::default()
+                }
+            ),
+            Some(None)
+        );
+        assert_eq!(ValueKind::Array.to_string(), "Array");
+    }
+
+    #[test]
+    fn trailing_key() {
+        let opts = Options {
+            trailing_key: Some("trailing".into()),
+            ..Default::default()
+        };
+        let config = r#"trailing = ["-v", "--flag", 1]
+name = "foo"
+
+[server]
+port = 8080"#;
+        assert_eq!(
+            Config::from_slice_with(config, &opts).unwrap(),
+            vec![
+                "--name=foo",
+                "--server.port=8080",
+                "--",
+                "-v",
+                "--flag",
+                "1"
+            ]
+        );
+        assert_eq!(
+            Config::from_slice(config).unwrap(),
+            vec![
+                "--name=foo",
+                "--server.port=8080",
+                "--trailing=-v",
+                "--trailing=--flag",
+                "--trailing=1"
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with("name = \"foo\"", &opts).unwrap(),
+            vec!["--name=foo"]
+        );
+        assert_eq!(
+            Config::from_slice_with("trailing = \"foo\"", &opts)
+                .unwrap_err()
+                .to_string(),
+            "field `trailing` is not an array at line 1, column 12"
+        );
+    }
+
+    #[test]
+    fn naming() {
+        #[derive(Debug)]
+        struct Abbreviate;
+
+        impl NamingStrategy for Abbreviate {
+            fn flag_name(&self, key: &str) -> String {
+                key.split('.')
+                    .map(|s| s.split('_').map(|w| &w[..1]).collect::<String>())
+                    .collect::<Vec<_>>()
+                    .join("-")
+            }
+        }
+
+        let config = r#"max_retries = 3
+enabled = true
+tags = ["a", "b"]
+
+[server]
+listen_port = 8080"#;
+        let opts = |naming: Arc<dyn NamingStrategy>| Options {
+            naming: Some(naming),
+            separate_value: Some(|k| k == "server.listen_port"),
+            negatable_keys: ["enabled"].map(String::from).into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(config, &opts(Arc::new(Abbreviate))).unwrap(),
+            vec!["--e", "--mr=3", "--s-lp", "8080", "--t=a", "--t=b"]
+        );
+        assert_eq!(
+            Config::from_slice_with(config, &opts(Arc::new(KebabCase))).unwrap(),
+            vec![
+                "--enabled",
+                "--max-retries=3",
+                "--server.listen-port",
+                "8080",
+                "--tags=a",
+                "--tags=b",
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(config, &opts(Arc::new(Prefixed("app-".into())))).unwrap(),
+            vec![
+                "--app-enabled",
+                "--app-max_retries=3",
+                "--app-server.listen_port",
+                "8080",
+                "--app-tags=a",
+                "--app-tags=b",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_array() {
+        let config = r#"tags = []
+joined = []
+name = "foo"
+
+[server]
+hosts = []"#;
+        let opts = |empty_array| Options {
+            empty_array,
+            joined_keys: [("joined".into(), ",".into())].into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(config, &opts(EmptyArray::Drop)).unwrap(),
+            vec!["--name=foo"]
+        );
+        assert_eq!(
+            Config::from_slice_with(config, &opts(EmptyArray::Empty)).unwrap(),
+            vec!["--joined=", "--name=foo", "--server.hosts=", "--tags="]
+        );
+        assert_eq!(
+            Config::from_slice_with(config, &opts(EmptyArray::Error))
+                .unwrap_err()
+                .to_string(),
+            "empty array not supported for field `joined` at line 2, column 10"
+        );
+    }
+
+    #[test]
+    fn from_bytes() {
+        let buf: Vec<u8> = r#"string = "foo""#.into();
+        assert_eq!(Config::from_bytes(&buf).unwrap(), vec!["--string=foo"]);
+        assert_eq!(Config::from_bytes(&buf[..0]).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn separator() {
+        let config = r#"offset = -5
+name = "foo"
+flag = true
+pattern = "-v"
+empty = """#;
+        let opts = |separator| Options {
+            separator,
+            separate_value: Some(|k| k == "pattern"),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(config, &opts(Separator::Equals)).unwrap(),
+            vec![
+                "--empty=",
+                "--flag",
+                "--name=foo",
+                "--offset=-5",
+                "--pattern",
+                "-v"
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(config, &opts(Separator::Space)).unwrap(),
+            vec![
+                "--empty",
+                "",
+                "--flag",
+                "--name",
+                "foo",
+                "--offset",
+                "-5",
+                "--pattern",
+                "-v"
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(config, &opts(Separator::Smart)).unwrap(),
+            vec![
+                "--empty",
+                "",
+                "--flag",
+                "--name",
+                "foo",
+                "--offset=-5",
+                "--pattern",
+                "-v"
+            ]
+        );
+    }
+
+    #[test]
+    fn non_finite_keys() {
+        let opts = Options {
+            non_finite_keys: HashSet::from(["threshold".into(), "min".into(), "factor".into()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with("threshold = inf\nmin = -inf\nfactor = nan", &opts).unwrap(),
+            vec!["--factor=nan", "--min=-inf", "--threshold=inf"]
+        );
+        let err = Config::from_slice("threshold = +inf").unwrap_err();
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<Error>())
+            .unwrap();
+        assert_eq!(err.key, "threshold");
+        assert_eq!(err.kind, Some(ValueKind::Float));
+        assert!(Config::from_slice("factor = nan").is_err());
+    }
+
+    #[test]
+    fn omit_defaults() {
+        let opts = Options {
+            defaults: Table::from_iter([
+                ("port".into(), Value::Integer(8080)),
+                ("host".into(), Value::String("localhost".into())),
+                ("verbose".into(), Value::Boolean(false)),
+            ]),
+            omit_defaults: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with("host = \"localhost\"\nport = 80\nname = \"foo\"", &opts)
+                .unwrap(),
+            vec!["--name=foo", "--port=80"]
+        );
+        assert!(
+            Config::from_slice_with("port = 8080\nverbose = false", &opts)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let opts = Options {
+            defaults: Table::from_iter([
+                ("port".into(), Value::Integer(8080)),
+                ("host".into(), Value::String("localhost".into())),
+                ("verbose".into(), Value::Boolean(true)),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(r#"host = "example.com""#, &opts).unwrap(),
+            vec!["--host=example.com", "--port=8080", "--verbose"]
+        );
+        assert_eq!(
+            Config::from_slice_with("port = 80\nverbose = false", &opts).unwrap(),
+            vec!["--port=80", "--host=localhost"]
+        );
+    }
+
+    #[test]
+    fn separator_array_spaces() {
+        let opts = Options {
+            separator: Separator::Space,
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(r#"names = ["John Doe", "Jane", " padded "]"#, &opts).unwrap(),
+            vec!["--names", "John Doe", "--names", "Jane", "--names", " padded "]
+        );
+    }
+
+    #[test]
+    fn reserved_key_collision() {
+        let config = r#"args = ["a", "b"]"#;
+        let known_keys = Some(HashSet::from(["args".into()]));
+        assert_eq!(
+            Config::from_slice_with(
+                config,
+                &Options {
+                    known_keys: known_keys.clone(),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec!["--args=a", "--args=b"]
+        );
+        let err = Config::from_slice_with(
+            config,
+            &Options {
+                known_keys,
+                trailing_key: Some("args".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "reserved key `args` collides with a regular key"
+        );
+        assert!(Config::from_slice_with(
+            config,
+            &Options {
+                defaults: Table::from_iter([("args".into(), Value::Boolean(true))]),
+                trailing_key: Some("args".into()),
+                ..Default::default()
+            },
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn env_vars() {
+        let opts = Options {
+            env_vars: true,
+            separator: Separator::Space,
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"db_host = "x"
+verbose = true
+quiet = false
+ports = [1, 2]
+
+[server]
+max-conns = 10"#,
+                &opts
+            )
+            .unwrap(),
+            vec![
+                "DB_HOST=x",
+                "PORTS=1",
+                "PORTS=2",
+                "SERVER_MAX_CONNS=10",
+                "VERBOSE=true"
+            ]
+        );
+        for opts in [
+            Options {
+                array_flag: ArrayFlag::Once,
+                ..opts.clone()
+            },
+            Options {
+                numeric_positionals: true,
+                ..opts.clone()
+            },
+            Options {
+                trailing_key: Some("trailing".into()),
+                ..opts
+            },
+        ] {
+            assert_eq!(
+                Config::from_slice_with("ports = [1, 2]", &opts)
+                    .unwrap_err()
+                    .kind(),
+                io::ErrorKind::InvalidInput
+            );
+        }
+    }
+
+    #[test]
+    fn trailing_data() {
+        assert_eq!(
+            Config::from_slice("a = 1\nb = 2\ngarbage here\n!!")
+                .unwrap_err()
+                .to_string(),
+            "failed to parse TOML: trailing data after a valid document at line 3, column 9: expected an equals, found an identifier at line 3 column 9"
+        );
+        assert_eq!(
+            Config::from_slice("a = 1\n[t]\nx = 1\n[t]\nx = 2")
+                .unwrap_err()
+                .to_string(),
+            "failed to parse TOML: redefinition of table `t` for key `t` at line 4 column 1"
+        );
+        assert_eq!(
+            Config::from_slice("a = 1\na = 2").unwrap_err().to_string(),
+            "failed to parse TOML: duplicate key: `a` at line 1 column 1"
+        );
+        assert_eq!(
+            Config::from_slice("a = 1\nb = \"unterminated")
+                .unwrap_err()
+                .to_string(),
+            "failed to parse TOML: unterminated string at line 2 column 5"
+        );
+        assert_eq!(
+            Config::from_slice("a = 1\nb = \nc = 3")
+                .unwrap_err()
+                .to_string(),
+            "failed to parse TOML: expected a value, found a newline at line 2 column 5"
+        );
+    }
+
+    #[test]
+    fn dotted_keys() {
+        let expected = vec!["--server.port=8080", "--server.tls.enabled"];
+        assert_eq!(
+            Config::from_slice("server.port = 8080\nserver.tls.enabled = true").unwrap(),
+            expected
+        );
+        assert_eq!(
+            Config::from_slice("[server]\nport = 8080\ntls.enabled = true").unwrap(),
+            expected
+        );
+        assert_eq!(
+            Config::from_slice("server = { port = 8080, tls = { enabled = true } }").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn flag_prefix() {
+        let config = r#"port = 8080
+max_retries = 3
+hosts = ["a", "b"]"#;
+        assert_eq!(
+            Config::from_slice_with(
+                config,
+                &Options {
+                    flag_prefix: Some("app-".into()),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec![
+                "--app-hosts=a",
+                "--app-hosts=b",
+                "--app-max_retries=3",
+                "--app-port=8080"
+            ]
+        );
+        assert_eq!(
+            Config::from_slice_with(
+                config,
+                &Options {
+                    flag_prefix: Some("app-".into()),
+                    naming: Some(Arc::new(KebabCase)),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec![
+                "--app-hosts=a",
+                "--app-hosts=b",
+                "--app-max-retries=3",
+                "--app-port=8080"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_value() {
+        let value =
+            Config::parse_value(b"\xEF\xBB\xBFport = 8080\n[server]\nhost = \"a\"").unwrap();
+        let Value::Table(mut kv) = value else {
+            panic!("value is not a table")
+        };
+        assert_eq!(kv.remove("port"), Some(Value::Integer(8080)));
+        assert_eq!(Config::from_table(kv).unwrap(), vec!["--server.host=a"]);
+        assert!(Config::parse_value("port =").is_err());
+    }
+
+    #[test]
+    fn conditions() {
+        let config = r#"port = 8080
+host = "localhost"
+
+[server]
+workers = 1
+tls = false
+
+[if.production]
+port = 80
+server = { workers = 8 }
+
+[if.staging]
+host = "staging"
+"#;
+        let opts = |active: &[&str]| Options {
+            condition_key: Some("if".into()),
+            active_conditions: active.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(config, &opts(&["production"])).unwrap(),
+            vec!["--host=localhost", "--port=80", "--server.workers=8"]
+        );
+        assert_eq!(
+            Config::from_slice_with(config, &opts(&["staging"])).unwrap(),
+            vec!["--host=staging", "--port=8080", "--server.workers=1"]
+        );
+        assert_eq!(
+            Config::from_slice_with(config, &opts(&[])).unwrap(),
+            vec!["--host=localhost", "--port=8080", "--server.workers=1"]
+        );
+        assert_eq!(
+            Config::from_slice_with("[if]\nproduction = 1", &opts(&["production"]))
+                .unwrap_err()
+                .to_string(),
+            "field `if.production` is not a table at line 2, column 14"
+        );
+    }
+
+    #[test]
+    fn separator_in_value() {
+        let config = r#"key = "a=b"
+list = ["c=d", "=e"]"#;
+        let args = |separator| {
+            Config::from_slice_with(
+                config,
+                &Options {
+                    separator,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let eq = args(Separator::Equals);
+        assert_eq!(eq, vec!["--key=a=b", "--list=c=d", "--list==e"]);
+        assert_eq!(
+            args(Separator::Space),
+            vec!["--key", "a=b", "--list", "c=d", "--list", "=e"]
+        );
+        assert_eq!(
+            crate::split_eq(eq.clone().into_iter()).collect::<Vec<_>>(),
+            args(Separator::Space)
+        );
+        assert_eq!(crate::to_shell_string(eq), "--key=a=b --list=c=d --list==e");
+    }
+
+    #[test]
+    fn join_separator() {
+        let config = r#"[server]
+max_conns = 10
+hosts = ["a"]
+
+[[server.plugins]]
+name = "p"
+"#;
+        let args = |join_separator| {
+            Config::from_slice_with(
+                config,
+                &Options {
+                    join_separator,
+                    naming: Some(Arc::new(KebabCase)),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            args(None),
+            vec![
+                "--server.hosts=a",
+                "--server.max-conns=10",
+                "--server.plugins.0.name=p"
+            ]
+        );
+        assert_eq!(args(Some('.')), args(None));
+        assert_eq!(
+            args(Some('-')),
+            vec![
+                "--server-hosts=a",
+                "--server-max-conns=10",
+                "--server-plugins-0-name=p"
+            ]
+        );
+        assert_eq!(
+            args(Some('_')),
+            vec![
+                "--server_hosts=a",
+                "--server_max-conns=10",
+                "--server_plugins_0_name=p"
+            ]
+        );
+    }
+
+    #[test]
+    fn value_kinds() {
+        let opts = Options {
+            value_kinds: HashMap::from([
+                ("port".into(), ValueKind::Integer),
+                ("name".into(), ValueKind::String),
+                ("hosts".into(), ValueKind::Array),
+                ("server.tls".into(), ValueKind::Boolean),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                "port = 80\nname = \"foo\"\nhosts = [\"a\"]\nserver.tls = true",
+                &opts
+            )
+            .unwrap(),
+            vec!["--hosts=a", "--name=foo", "--port=80", "--server.tls"]
+        );
+        let err = Config::from_slice_with(r#"port = "eighty""#, &opts).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected Integer for field `port`, found String at line 1, column 8"
+        );
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<Error>())
+            .unwrap();
+        assert_eq!(err.key, "port");
+        assert_eq!(err.kind, Some(ValueKind::String));
+        assert!(Config::from_slice_with("hosts = \"a\"", &opts).is_err());
+        assert!(Config::from_slice_with("[server]\ntls = 1", &opts).is_err());
+    }
+
+    #[test]
+    fn pair_tables() {
+        let opts = Options {
+            pair_tables: HashMap::from([
+                ("labels".into(), PairTable::new("label")),
+                (
+                    "server.env".into(),
+                    PairTable {
+                        flag: "env".into(),
+                        separator: ':',
+                    },
+                ),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::from_slice_with(
+                r#"[labels]
+name = "web"
+replicas = 3
+
+[server.env]
+PATH = "/bin"
+"#,
+                &opts
+            )
+            .unwrap(),
+            vec!["--label=name=web", "--label=replicas=3", "--env=PATH:/bin"]
+        );
+        assert_eq!(
+            Config::from_slice_with(
+                r#"[server.env]
+PATH = "/bin"
+"#,
+                &Options {
+                    flag_prefix: Some("app-".into()),
+                    naming: Some(Arc::new(KebabCase)),
+                    join_separator: Some('-'),
+                    pair_tables: HashMap::from([(
+                        "server.env".into(),
+                        PairTable::new("server_env"),
+                    )]),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec!["--server_env=PATH=/bin"]
+        );
+        let err = Config::from_slice_with("[labels]\nname = [1]", &opts).unwrap_err();
+        let err = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<Error>())
+            .unwrap();
+        assert_eq!(err.key, "labels.name");
+        assert_eq!(err.kind, Some(ValueKind::Array));
     }
 }